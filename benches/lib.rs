@@ -7,6 +7,24 @@ mod tests {
     use test::Bencher;
     use tlv_parser::tlv::*;
 
+    /// Chain of `depth` constructed data objects, each with a primitive sibling
+    fn deep_tree(depth: usize) -> Tlv {
+        let mut tlv = Tlv::new(0x5A, Value::Val(vec![0x47; 8])).unwrap();
+        for i in 0..depth {
+            let sibling = Tlv::new(0x9F02, Value::Val(vec![i as u8; 6])).unwrap();
+            tlv = Tlv::new(0xBF0C, Value::TlvList(vec![sibling, tlv])).unwrap();
+        }
+        tlv
+    }
+
+    /// Constructed data object with `width` primitive children
+    fn wide_tree(width: usize) -> Tlv {
+        let list = (0..width)
+            .map(|i| Tlv::new(0x9F02, Value::Val(vec![i as u8; 6])).unwrap())
+            .collect();
+        Tlv::new(0x70, Value::TlvList(list)).unwrap()
+    }
+
     #[bench]
     fn bench_from_vec(b: &mut Bencher) {
         let tlv_vec = vec![
@@ -18,4 +36,28 @@ mod tests {
         ];
         b.iter(|| Tlv::from_vec(&tlv_vec))
     }
+
+    #[bench]
+    fn bench_to_vec_deep(b: &mut Bencher) {
+        let tlv = deep_tree(500);
+        b.iter(|| tlv.to_vec())
+    }
+
+    #[bench]
+    fn bench_to_vec_wide(b: &mut Bencher) {
+        let tlv = wide_tree(5000);
+        b.iter(|| tlv.to_vec())
+    }
+
+    #[bench]
+    fn bench_len_deep(b: &mut Bencher) {
+        let tlv = deep_tree(500);
+        b.iter(|| tlv.len())
+    }
+
+    #[bench]
+    fn bench_len_wide(b: &mut Bencher) {
+        let tlv = wide_tree(5000);
+        b.iter(|| tlv.len())
+    }
 }
@@ -1,9 +1,42 @@
 use std::io::Read;
+use tlv_parser::bitfield::{Aip, Auc, Ctq, TerminalCapabilities, Tsi, Ttq, Tvr};
+use tlv_parser::cvm::CvmList;
+use tlv_parser::diff::diff_list;
+use tlv_parser::iad::IadRegistry;
+use tlv_parser::oid::Oid;
 use tlv_parser::tlv::{Tlv, Value};
+use tlv_parser::TlvError;
 
 use hex::FromHex;
 
-fn print(tlv: &Tlv, ident: usize) {
+/// Returns human-readable description of known EMV data objects
+fn annotate(tlv: &Tlv, registry: &IadRegistry) -> Option<String> {
+    let val = tlv.val();
+
+    match tlv.tag() {
+        Tvr::TAG => Tvr::try_from(val).ok().map(|x| x.to_string()),
+        Tsi::TAG => Tsi::try_from(val).ok().map(|x| x.to_string()),
+        Aip::TAG => Aip::try_from(val).ok().map(|x| x.to_string()),
+        Auc::TAG => Auc::try_from(val).ok().map(|x| x.to_string()),
+        Ctq::TAG => Ctq::try_from(val).ok().map(|x| x.to_string()),
+        Ttq::TAG => Ttq::try_from(val).ok().map(|x| x.to_string()),
+        TerminalCapabilities::TAG => TerminalCapabilities::try_from(val)
+            .ok()
+            .map(|x| x.to_string()),
+        tlv_parser::cvm::CVM_LIST_TAG => CvmList::from_tlv(tlv).ok().map(|x| x.to_string()),
+        tlv_parser::asn1::OID_TAG => Oid::from_tlv(tlv).ok().map(|oid| match oid.name() {
+            Some(name) => format!("{oid} ({name})"),
+            None => oid.to_string(),
+        }),
+        tlv_parser::iad::IAD_TAG => match *val {
+            Value::Val(ref v) => registry.decode(v).map(|x| x.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn print(tlv: &Tlv, ident: usize, registry: Option<&IadRegistry>) {
     for _ in 0..ident {
         print!(" ");
     }
@@ -13,30 +46,85 @@ fn print(tlv: &Tlv, ident: usize) {
             println!("tag={:02X}", tlv.tag());
 
             for t in list {
-                print(t, ident + 2);
+                print(t, ident + 2, registry);
             }
         }
         Value::Val(_) => {
             println!("{}", tlv);
+
+            if let Some(text) = registry.and_then(|r| annotate(tlv, r)) {
+                for line in text.lines() {
+                    println!("{:width$}# {}", "", line, width = ident + 2);
+                }
+            }
         }
         _ => (),
     }
 }
 
+/// Converts hex dump (non-hex characters are ignored) to bytes
+fn from_hex(input: &str) -> Vec<u8> {
+    let input = input.replace(|ch: char| !ch.is_ascii_hexdigit(), "");
+    FromHex::from_hex(&input).unwrap()
+}
+
+/// Parses all concatenated data objects
+fn parse_tlvs(buf: &[u8]) -> Result<Vec<Tlv>, TlvError> {
+    let mut tlvs = Vec::new();
+    let mut rest = buf;
+
+    while !rest.is_empty() {
+        let (tlv, next) = Tlv::from_vec_partial(rest)?;
+        rest = next;
+        tlvs.push(tlv);
+    }
+
+    Ok(tlvs)
+}
+
+/// Parses all data objects of the file for `diff` subcommand
+fn read_tlvs(path: &str) -> Vec<Tlv> {
+    let buf = from_hex(&std::fs::read_to_string(path).unwrap());
+    match parse_tlvs(&buf) {
+        Ok(tlvs) => tlvs,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            std::process::exit(2);
+        }
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("diff") {
+        if args.len() != 3 {
+            eprintln!("Usage: decode-tlv diff OLD NEW");
+            std::process::exit(2);
+        }
+
+        let changes = diff_list(&read_tlvs(&args[1]), &read_tlvs(&args[2]));
+        for change in &changes {
+            println!("{}", change);
+        }
+        std::process::exit(if changes.is_empty() { 0 } else { 1 });
+    }
+
+    let annotated = args.iter().any(|x| x == "-a" || x == "--annotate");
+    let registry = IadRegistry::default();
+
     let mut input = String::new();
     std::io::stdin().read_to_string(&mut input).unwrap();
-    input = input.replace(|ch: char| !ch.is_ascii_hexdigit(), "");
 
-    let buf: Vec<u8> = FromHex::from_hex(&input).unwrap();
-    let mut idx = 0;
+    let buf = from_hex(&input);
+    let mut rest = &buf[..];
 
-    while idx < buf.len() {
-        match Tlv::from_vec(&buf[idx..]) {
-            Ok(tlv) => {
-                print(&tlv, 0);
+    while !rest.is_empty() {
+        match Tlv::from_vec_partial(rest) {
+            Ok((tlv, next)) => {
+                print(&tlv, 0, annotated.then_some(&registry));
                 println!();
-                idx += tlv.len();
+                rest = next;
             }
             Err(err) => {
                 println!("Error: {}", err);
@@ -45,3 +133,31 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotate_oid_test() {
+        let registry = IadRegistry::default();
+        let tlv = Tlv::from_vec(&from_hex("06 03 55 1D 13")).unwrap();
+        assert_eq!(
+            annotate(&tlv, &registry).as_deref(),
+            Some("2.5.29.19 (basicConstraints)")
+        );
+        assert!(!tlv.to_string().contains("2.5.29.19"));
+    }
+
+    #[test]
+    fn diff_padded_test() {
+        let old = parse_tlvs(&from_hex("70 03 5A 01 47  9F 36 02 00 01")).unwrap();
+        let new = parse_tlvs(&from_hex("00 70 03 5A 01 47 00 00  9F 36 02 00 02")).unwrap();
+
+        let changes = diff_list(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to_string(), "- 9F36: 0001\n+ 9F36: 0002");
+
+        assert!(parse_tlvs(&from_hex("70 05 5A 01 47 00 00")).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Application File Locator (AFL, tag `94`) decoding.
+//!
+//! AFL is a list of 4-byte entries, each one defines a range of records of an
+//! Application Elementary File which terminal shall read. See EMV 4.3 Book 3, section 10.2.
+
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use crate::apdu::Command;
+use crate::tlv::{Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Tag number of Application File Locator
+pub const AFL_TAG: usize = 0x94;
+
+/// Single entry of AFL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AflEntry {
+    /// Short File Identifier
+    pub sfi: u8,
+    /// Number of the first record to read
+    pub first_record: u8,
+    /// Number of the last record to read
+    pub last_record: u8,
+    /// Number of records involved in offline data authentication,
+    /// starting with the first record
+    pub oda_records: u8,
+}
+
+impl AflEntry {
+    /// Parses and validates single 4-byte AFL entry
+    pub fn from_bytes(entry: [u8; 4]) -> Result<AflEntry> {
+        let entry = AflEntry {
+            // three least significant bits of the first byte are RFU
+            sfi: entry[0] >> 3,
+            first_record: entry[1],
+            last_record: entry[2],
+            oda_records: entry[3],
+        };
+
+        let valid = entry.sfi != 0
+            && entry.sfi <= 30
+            && entry.first_record != 0
+            && entry.last_record >= entry.first_record
+            && entry.oda_records as usize <= entry.records().len();
+
+        if !valid {
+            return Err(TlvError::InvalidValue {
+                tag_number: AFL_TAG,
+            });
+        }
+
+        Ok(entry)
+    }
+
+    /// Returns entry encoded as 4 bytes
+    pub fn to_bytes(&self) -> [u8; 4] {
+        [
+            self.sfi << 3,
+            self.first_record,
+            self.last_record,
+            self.oda_records,
+        ]
+    }
+
+    /// Returns numbers of records to read
+    pub fn records(&self) -> RangeInclusive<u8> {
+        self.first_record..=self.last_record
+    }
+
+    /// Returns numbers of records involved in offline data authentication
+    pub fn oda_records(&self) -> impl Iterator<Item = u8> {
+        self.records().take(self.oda_records as usize)
+    }
+}
+
+/// Application File Locator
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Afl {
+    entries: Vec<AflEntry>,
+}
+
+impl Afl {
+    /// Parses AFL from value of data object `94`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::afl::*;
+    /// let afl = Afl::from_vec(&[0x08, 0x01, 0x01, 0x00, 0x10, 0x01, 0x03, 0x02]).unwrap();
+    /// assert_eq!(afl.entries().len(), 2);
+    /// assert_eq!(afl.entries()[1].sfi, 2);
+    /// assert_eq!(afl.records(), vec![(1, 1), (2, 1), (2, 2), (2, 3)]);
+    /// assert_eq!(afl.oda_records(), vec![(2, 1), (2, 2)]);
+    /// ```
+    pub fn from_vec(afl: &[u8]) -> Result<Afl> {
+        let chunks = afl.chunks_exact(4);
+        if !chunks.remainder().is_empty() {
+            return Err(TlvError::InvalidValue {
+                tag_number: AFL_TAG,
+            });
+        }
+
+        let entries = chunks
+            .map(|x| AflEntry::from_bytes([x[0], x[1], x[2], x[3]]))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Afl { entries })
+    }
+
+    /// Parses AFL from value of TLV
+    pub fn from_value(value: &Value) -> Result<Afl> {
+        match *value {
+            Value::Val(ref v) => Afl::from_vec(v),
+            Value::Nothing => Ok(Afl::default()),
+            Value::TlvList(_) => Err(TlvError::ValExpected {
+                tag_number: AFL_TAG,
+            }),
+            Value::Invalid { ref error, .. } => Err(error.clone()),
+        }
+    }
+
+    /// Parses AFL from data object `94`
+    pub fn from_tlv(tlv: &Tlv) -> Result<Afl> {
+        if tlv.tag() != AFL_TAG {
+            return Err(TlvError::InvalidTagNumber);
+        }
+
+        Afl::from_value(tlv.val())
+    }
+
+    /// Returns entries of AFL
+    pub fn entries(&self) -> &[AflEntry] {
+        &self.entries
+    }
+
+    /// Returns AFL encoded as array of bytes
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|e| e.to_bytes()).collect()
+    }
+
+    /// Returns `(sfi, record)` pairs of all records to read
+    pub fn records(&self) -> Vec<(u8, u8)> {
+        self.entries
+            .iter()
+            .flat_map(|e| e.records().map(move |r| (e.sfi, r)))
+            .collect()
+    }
+
+    /// Returns `(sfi, record)` pairs of records to include in offline data authentication
+    pub fn oda_records(&self) -> Vec<(u8, u8)> {
+        self.entries
+            .iter()
+            .flat_map(|e| e.oda_records().map(move |r| (e.sfi, r)))
+            .collect()
+    }
+
+    /// Returns READ RECORD commands for all records in AFL order
+    pub fn read_record_commands(&self) -> Vec<Command> {
+        self.records()
+            .into_iter()
+            .map(|(sfi, record)| Command::read_record(sfi, record))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn from_tlv_test() {
+        // AFL from Amex CDA trace GPO response
+        let tlv = Tlv::from_vec(&[
+            0x94, 0x14, 0x08, 0x01, 0x01, 0x00, 0x08, 0x02, 0x02, 0x01, 0x08, 0x04, 0x05, 0x00,
+            0x10, 0x02, 0x03, 0x00, 0x18, 0x03, 0x03, 0x00,
+        ])
+        .unwrap();
+        let afl = Afl::from_tlv(&tlv).unwrap();
+
+        assert_eq!(afl.entries().len(), 5);
+        assert_eq!(
+            afl.entries()[2],
+            AflEntry {
+                sfi: 1,
+                first_record: 4,
+                last_record: 5,
+                oda_records: 0
+            }
+        );
+        assert_eq!(afl.oda_records(), vec![(1, 2)]);
+        assert_eq!(afl.records().len(), 7);
+        assert_eq!(afl.to_vec(), tlv.val().to_vec());
+
+        let commands = afl.read_record_commands();
+        assert_eq!(commands.len(), 7);
+        assert_eq!(
+            commands[5].to_vec().unwrap(),
+            vec![0x00, 0xB2, 0x03, 0x14, 0x00]
+        );
+        assert_eq!(
+            commands[6].to_vec().unwrap(),
+            vec![0x00, 0xB2, 0x03, 0x1C, 0x00]
+        );
+    }
+
+    #[test]
+    fn validation_test() {
+        // not multiple of 4
+        assert!(Afl::from_vec(&[0x08, 0x01, 0x01]).is_err());
+        // SFI 0
+        assert!(Afl::from_vec(&[0x00, 0x01, 0x01, 0x00]).is_err());
+        // SFI 31
+        assert!(Afl::from_vec(&[0xF8, 0x01, 0x01, 0x00]).is_err());
+        // first record 0
+        assert!(Afl::from_vec(&[0x08, 0x00, 0x01, 0x00]).is_err());
+        // last record less than first record
+        assert!(Afl::from_vec(&[0x08, 0x02, 0x01, 0x00]).is_err());
+        // too many records for ODA
+        assert!(Afl::from_vec(&[0x08, 0x01, 0x02, 0x03]).is_err());
+
+        assert!(Afl::from_vec(&[]).unwrap().entries().is_empty());
+    }
+}
@@ -0,0 +1,401 @@
+//! Command and response APDUs as defined in ISO/IEC 7816-4.
+
+use alloc::vec::Vec;
+
+use crate::tlv::Tlv;
+use crate::{Result, TlvError};
+
+/// Classification of status word (SW1 SW2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// `9000`: normal processing
+    Success,
+    /// `61xx`: SW2 encodes number of response bytes still available
+    MoreData(u8),
+    /// `62xx` or `63xx`: processed with warning
+    Warning { sw1: u8, sw2: u8 },
+    /// `6Cxx`: wrong Le field, SW2 encodes exact number of available data bytes
+    WrongLe(u8),
+    /// Any other status word
+    Error { sw1: u8, sw2: u8 },
+}
+
+impl Status {
+    /// Classifies status word
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::apdu::Status;
+    /// assert_eq!(Status::new(0x90, 0x00), Status::Success);
+    /// assert_eq!(Status::new(0x6C, 0x10), Status::WrongLe(0x10));
+    /// assert!(Status::new(0x63, 0xC2).is_warning());
+    /// ```
+    pub fn new(sw1: u8, sw2: u8) -> Status {
+        match (sw1, sw2) {
+            (0x90, 0x00) => Status::Success,
+            (0x61, _) => Status::MoreData(sw2),
+            (0x62, _) | (0x63, _) => Status::Warning { sw1, sw2 },
+            (0x6C, _) => Status::WrongLe(sw2),
+            _ => Status::Error { sw1, sw2 },
+        }
+    }
+
+    /// Returns true if status is `9000`
+    pub fn is_success(&self) -> bool {
+        *self == Status::Success
+    }
+
+    /// Returns true if status is `62xx` or `63xx`
+    pub fn is_warning(&self) -> bool {
+        matches!(*self, Status::Warning { .. })
+    }
+}
+
+/// Response APDU: optional data field followed by status word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub data: Vec<u8>,
+    pub sw1: u8,
+    pub sw2: u8,
+}
+
+impl Response {
+    /// Parses response APDU, the last two bytes are treated as SW1 SW2
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::apdu::*;
+    /// let resp = Response::from_vec(&[0x80, 0x02, 0x5C, 0x00, 0x90, 0x00]).unwrap();
+    /// assert_eq!(resp.data, vec![0x80, 0x02, 0x5C, 0x00]);
+    /// assert_eq!(resp.sw(), 0x9000);
+    /// assert!(resp.is_success());
+    /// ```
+    pub fn from_vec(resp: &[u8]) -> Result<Response> {
+        if resp.len() < 2 {
+            return Err(TlvError::TruncatedTlv);
+        }
+
+        let (data, sw) = resp.split_at(resp.len() - 2);
+        Ok(Response {
+            data: data.to_vec(),
+            sw1: sw[0],
+            sw2: sw[1],
+        })
+    }
+
+    /// Returns status word as single number
+    pub fn sw(&self) -> u16 {
+        u16::from_be_bytes([self.sw1, self.sw2])
+    }
+
+    /// Returns classification of status word
+    pub fn status(&self) -> Status {
+        Status::new(self.sw1, self.sw2)
+    }
+
+    /// Returns true if status word is `9000`
+    pub fn is_success(&self) -> bool {
+        self.status().is_success()
+    }
+
+    /// Parses data field as TLV
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::apdu::*;
+    /// let resp = Response::from_vec(&[0x70, 0x03, 0x5F, 0x34, 0x00, 0x90, 0x00]).unwrap();
+    /// assert_eq!(resp.tlv().unwrap().tag(), 0x70);
+    /// ```
+    pub fn tlv(&self) -> Result<Tlv> {
+        Tlv::from_vec(&self.data)
+    }
+
+    /// Returns response APDU as array of bytes
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = self.data.clone();
+        out.push(self.sw1);
+        out.push(self.sw2);
+        out
+    }
+}
+
+/// Case of command APDU per ISO/IEC 7816-3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    /// No command data, no response data
+    Case1,
+    /// No command data, response data expected
+    Case2,
+    /// Command data, no response data
+    Case3,
+    /// Command data, response data expected
+    Case4,
+}
+
+/// Command APDU
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::apdu::*;
+/// // SELECT PPSE
+/// let cmd = Command::new(0x00, 0xA4, 0x04, 0x00)
+///     .with_data(b"2PAY.SYS.DDF01".to_vec())
+///     .with_le(256);
+/// assert_eq!(cmd.case(), Case::Case4);
+///
+/// let apdu = cmd.to_vec().unwrap();
+/// assert_eq!(&apdu[..5], [0x00, 0xA4, 0x04, 0x00, 0x0E]);
+/// assert_eq!(apdu[apdu.len() - 1], 0x00);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    pub cla: u8,
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    data: Vec<u8>,
+    le: Option<usize>,
+}
+
+impl Command {
+    /// Maximum length of data field of command APDU
+    pub const MAX_LC: usize = 0xFFFF;
+
+    /// Maximum number of expected response bytes
+    pub const MAX_LE: usize = 0x10000;
+
+    /// Creates case 1 command APDU
+    pub fn new(cla: u8, ins: u8, p1: u8, p2: u8) -> Command {
+        Command {
+            cla,
+            ins,
+            p1,
+            p2,
+            data: Vec::new(),
+            le: None,
+        }
+    }
+
+    /// Creates READ RECORD command for record of file with given SFI
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::apdu::*;
+    /// let cmd = Command::read_record(1, 2);
+    /// assert_eq!(cmd.to_vec().unwrap(), vec![0x00, 0xB2, 0x02, 0x0C, 0x00]);
+    /// ```
+    pub fn read_record(sfi: u8, record: u8) -> Command {
+        Command::new(0x00, 0xB2, record, (sfi << 3) | 0x04).with_le(0x100)
+    }
+
+    /// Sets command data field
+    pub fn with_data(mut self, data: Vec<u8>) -> Command {
+        self.data = data;
+        self
+    }
+
+    /// Sets command data field to TLV-encoded object
+    pub fn with_tlv(self, tlv: &Tlv) -> Command {
+        self.with_data(tlv.to_vec())
+    }
+
+    /// Sets maximum number of expected response bytes (Ne)
+    pub fn with_le(mut self, le: usize) -> Command {
+        self.le = Some(le);
+        self
+    }
+
+    /// Returns command data field
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns maximum number of expected response bytes (Ne)
+    pub fn le(&self) -> Option<usize> {
+        self.le
+    }
+
+    /// Returns case of command APDU
+    pub fn case(&self) -> Case {
+        match (self.data.is_empty(), self.le.is_some()) {
+            (true, false) => Case::Case1,
+            (true, true) => Case::Case2,
+            (false, false) => Case::Case3,
+            (false, true) => Case::Case4,
+        }
+    }
+
+    /// Returns true if command requires extended Lc/Le fields
+    pub fn is_extended(&self) -> bool {
+        self.data.len() > 0xFF || self.le.is_some_and(|le| le > 0x100)
+    }
+
+    /// Returns command APDU as array of bytes
+    ///
+    /// Short Lc/Le fields are used unless data field or Le exceed their limits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::apdu::*;
+    /// let cmd = Command::new(0x00, 0xB0, 0x00, 0x00).with_le(0x1000);
+    /// assert_eq!(cmd.to_vec().unwrap(), vec![0x00, 0xB0, 0x00, 0x00, 0x00, 0x10, 0x00]);
+    /// ```
+    pub fn to_vec(&self) -> Result<Vec<u8>> {
+        if self.data.len() > Self::MAX_LC {
+            return Err(TlvError::InvalidLength);
+        }
+
+        if let Some(le) = self.le {
+            if le == 0 || le > Self::MAX_LE {
+                return Err(TlvError::InvalidLength);
+            }
+        }
+
+        let extended = self.is_extended();
+
+        let mut out = Vec::with_capacity(4 + 3 + self.data.len() + 3);
+        out.extend_from_slice(&[self.cla, self.ins, self.p1, self.p2]);
+
+        if !self.data.is_empty() {
+            if extended {
+                out.push(0x00);
+                out.extend_from_slice(&(self.data.len() as u16).to_be_bytes());
+            } else {
+                out.push(self.data.len() as u8);
+            }
+            out.extend_from_slice(&self.data);
+        }
+
+        if let Some(le) = self.le {
+            if extended {
+                // in case 2E Le field is prefixed with '00', in case 4E Lc field already has it
+                if self.data.is_empty() {
+                    out.push(0x00);
+                }
+                // Ne = 65536 is encoded as '0000'
+                out.extend_from_slice(&(le as u16).to_be_bytes());
+            } else {
+                // Ne = 256 is encoded as '00'
+                out.push(le as u8);
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tlv::Value;
+    use alloc::vec;
+
+    #[test]
+    fn response_test() {
+        // READ RECORD response from Amex CDA trace
+        let input = [
+            0x70, 0x1F, 0x9F, 0x32, 0x03, 0x01, 0x00, 0x01, 0x8F, 0x01, 0xA1, 0x92, 0x14, 0xF2,
+            0x68, 0xED, 0x44, 0x24, 0xA5, 0x39, 0x16, 0x61, 0x14, 0x20, 0x7B, 0xAD, 0xF4, 0x5E,
+            0x35, 0x64, 0x39, 0x7D, 0x67, 0x90, 0x00,
+        ];
+        let resp = Response::from_vec(&input).unwrap();
+        assert_eq!(resp.status(), Status::Success);
+        assert_eq!(resp.data.len(), input.len() - 2);
+
+        let tlv = resp.tlv().unwrap();
+        assert!(matches!(tlv.find_val("70 / 8F"), Some(Value::Val(v)) if *v == [0xA1]));
+        assert_eq!(resp.to_vec(), input);
+
+        assert!(Response::from_vec(&[0x90]).is_err());
+
+        let resp = Response::from_vec(&[0x6A, 0x82]).unwrap();
+        assert!(resp.data.is_empty());
+        assert_eq!(
+            resp.status(),
+            Status::Error {
+                sw1: 0x6A,
+                sw2: 0x82
+            }
+        );
+    }
+
+    #[test]
+    fn status_test() {
+        assert_eq!(Status::new(0x61, 0x1A), Status::MoreData(0x1A));
+        assert_eq!(
+            Status::new(0x62, 0x83),
+            Status::Warning {
+                sw1: 0x62,
+                sw2: 0x83
+            }
+        );
+        assert!(Status::new(0x63, 0x00).is_warning());
+        assert!(!Status::new(0x6C, 0x00).is_success());
+        assert!(!Status::new(0x90, 0x01).is_success());
+    }
+
+    #[test]
+    fn command_short_test() {
+        let cmd = Command::new(0x80, 0xAE, 0x80, 0x00);
+        assert_eq!(cmd.case(), Case::Case1);
+        assert_eq!(cmd.to_vec().unwrap(), vec![0x80, 0xAE, 0x80, 0x00]);
+
+        let cmd = Command::new(0x00, 0xB2, 0x01, 0x0C).with_le(256);
+        assert_eq!(cmd.case(), Case::Case2);
+        assert_eq!(cmd.to_vec().unwrap(), vec![0x00, 0xB2, 0x01, 0x0C, 0x00]);
+
+        let cmd = Command::new(0x00, 0x20, 0x00, 0x80).with_data(vec![0x24, 0x12, 0x34]);
+        assert_eq!(cmd.case(), Case::Case3);
+        assert_eq!(
+            cmd.to_vec().unwrap(),
+            vec![0x00, 0x20, 0x00, 0x80, 0x03, 0x24, 0x12, 0x34]
+        );
+
+        let pdol = Tlv::new(0x83, Value::Nothing).unwrap();
+        let cmd = Command::new(0x80, 0xA8, 0x00, 0x00)
+            .with_tlv(&pdol)
+            .with_le(256);
+        assert_eq!(cmd.case(), Case::Case4);
+        assert_eq!(
+            cmd.to_vec().unwrap(),
+            vec![0x80, 0xA8, 0x00, 0x00, 0x02, 0x83, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn command_extended_test() {
+        let cmd = Command::new(0x00, 0xB0, 0x00, 0x00).with_le(Command::MAX_LE);
+        assert!(cmd.is_extended());
+        assert_eq!(
+            cmd.to_vec().unwrap(),
+            vec![0x00, 0xB0, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+
+        let cmd = Command::new(0x00, 0xD6, 0x00, 0x00).with_data(vec![0xAA; 0x100]);
+        let apdu = cmd.to_vec().unwrap();
+        assert_eq!(&apdu[4..7], [0x00, 0x01, 0x00]);
+        assert_eq!(apdu.len(), 7 + 0x100);
+
+        let cmd = cmd.with_le(0x200);
+        let apdu = cmd.to_vec().unwrap();
+        assert_eq!(&apdu[4..7], [0x00, 0x01, 0x00]);
+        assert_eq!(&apdu[apdu.len() - 2..], [0x02, 0x00]);
+        assert_eq!(apdu.len(), 9 + 0x100);
+
+        assert!(Command::new(0, 0, 0, 0).with_le(0).to_vec().is_err());
+        assert!(Command::new(0, 0, 0, 0)
+            .with_le(Command::MAX_LE + 1)
+            .to_vec()
+            .is_err());
+        assert!(Command::new(0, 0, 0, 0)
+            .with_data(vec![0; Command::MAX_LC + 1])
+            .to_vec()
+            .is_err());
+    }
+}
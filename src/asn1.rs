@@ -0,0 +1,542 @@
+//! Decoders and encoders of ASN.1 universal types.
+//!
+//! Values are checked against DER constraints (ITU-T X.690, section 10 and 11):
+//! minimal INTEGER encoding, BOOLEAN coded as `00`/`FF`, zero unused bits of BIT STRING,
+//! `Z`-terminated times without fractional seconds, etc.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::oid::Oid;
+use crate::tlv::{Tag, Tlv, Value};
+use crate::{Result, TlvError};
+
+pub const BOOLEAN_TAG: Tag = 0x01;
+pub const INTEGER_TAG: Tag = 0x02;
+pub const BIT_STRING_TAG: Tag = 0x03;
+pub const OCTET_STRING_TAG: Tag = 0x04;
+pub const NULL_TAG: Tag = 0x05;
+pub const OID_TAG: Tag = 0x06;
+pub const UTF8_STRING_TAG: Tag = 0x0C;
+pub const PRINTABLE_STRING_TAG: Tag = 0x13;
+pub const IA5_STRING_TAG: Tag = 0x16;
+pub const UTC_TIME_TAG: Tag = 0x17;
+pub const GENERALIZED_TIME_TAG: Tag = 0x18;
+pub const SEQUENCE_TAG: Tag = 0x30;
+pub const SET_TAG: Tag = 0x31;
+
+/// Decodes BOOLEAN
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::Tlv;
+/// # use tlv_parser::asn1;
+/// let tlv = Tlv::from_vec(&[0x01, 0x01, 0xFF]).unwrap();
+/// assert!(asn1::decode_bool(&tlv).unwrap());
+/// assert_eq!(asn1::encode_bool(true), tlv);
+///
+/// // DER requires TRUE to be encoded as 'FF'
+/// assert!(asn1::decode_bool(&Tlv::from_vec(&[0x01, 0x01, 0x01]).unwrap()).is_err());
+/// ```
+pub fn decode_bool(tlv: &Tlv) -> Result<bool> {
+    match primitive(tlv, BOOLEAN_TAG)? {
+        [0x00] => Ok(false),
+        [0xFF] => Ok(true),
+        _ => Err(invalid(BOOLEAN_TAG)),
+    }
+}
+
+/// Encodes BOOLEAN
+pub fn encode_bool(val: bool) -> Tlv {
+    primitive_tlv(BOOLEAN_TAG, alloc::vec![if val { 0xFF } else { 0x00 }])
+}
+
+/// Returns two's complement content octets of INTEGER after checking minimal encoding
+pub fn decode_integer_bytes(tlv: &Tlv) -> Result<&[u8]> {
+    let val = primitive(tlv, INTEGER_TAG)?;
+    match val {
+        [] => Err(invalid(INTEGER_TAG)),
+        [0x00, next, ..] if next & 0x80 == 0 => Err(invalid(INTEGER_TAG)),
+        [0xFF, next, ..] if next & 0x80 != 0 => Err(invalid(INTEGER_TAG)),
+        _ => Ok(val),
+    }
+}
+
+/// Decodes INTEGER fitting into `i64`
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::Tlv;
+/// # use tlv_parser::asn1;
+/// assert_eq!(asn1::decode_i64(&Tlv::from_vec(&[0x02, 0x02, 0x00, 0x80]).unwrap()).unwrap(), 128);
+/// assert_eq!(asn1::encode_i64(-129).to_vec(), [0x02, 0x02, 0xFF, 0x7F]);
+/// ```
+pub fn decode_i64(tlv: &Tlv) -> Result<i64> {
+    let val = decode_integer_bytes(tlv)?;
+    if val.len() > 8 {
+        return Err(invalid(INTEGER_TAG));
+    }
+
+    let init = if val[0] & 0x80 != 0 { -1 } else { 0 };
+    Ok(val.iter().fold(init, |acc, &x| acc << 8 | x as i64))
+}
+
+/// Encodes INTEGER
+pub fn encode_i64(val: i64) -> Tlv {
+    let bytes = val.to_be_bytes();
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+
+    primitive_tlv(INTEGER_TAG, bytes[start..].to_vec())
+}
+
+/// Encodes non-negative INTEGER given as big-endian magnitude of arbitrary length
+pub fn encode_unsigned(magnitude: &[u8]) -> Tlv {
+    let start = magnitude.iter().take_while(|&&x| x == 0).count();
+    let magnitude = &magnitude[start..];
+
+    let mut val = Vec::with_capacity(magnitude.len() + 1);
+    if magnitude.first().map_or(true, |x| x & 0x80 != 0) {
+        val.push(0x00);
+    }
+    val.extend_from_slice(magnitude);
+
+    primitive_tlv(INTEGER_TAG, val)
+}
+
+/// Content of BIT STRING
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitString {
+    /// Number of unused bits in the last octet
+    pub unused_bits: u8,
+    pub data: Vec<u8>,
+}
+
+impl BitString {
+    /// Creates BIT STRING without unused bits
+    pub fn new(data: Vec<u8>) -> BitString {
+        BitString {
+            unused_bits: 0,
+            data,
+        }
+    }
+
+    /// Returns number of bits
+    pub fn bit_len(&self) -> usize {
+        self.data.len() * 8 - self.unused_bits as usize
+    }
+}
+
+/// Decodes BIT STRING
+pub fn decode_bit_string(tlv: &Tlv) -> Result<BitString> {
+    let (&unused_bits, data) = primitive(tlv, BIT_STRING_TAG)?
+        .split_first()
+        .ok_or(invalid(BIT_STRING_TAG))?;
+
+    let valid = match data.last() {
+        None => unused_bits == 0,
+        Some(&last) => unused_bits < 8 && last & ((1u16 << unused_bits) - 1) as u8 == 0,
+    };
+
+    if !valid {
+        return Err(invalid(BIT_STRING_TAG));
+    }
+
+    Ok(BitString {
+        unused_bits,
+        data: data.to_vec(),
+    })
+}
+
+/// Encodes BIT STRING
+pub fn encode_bit_string(val: &BitString) -> Result<Tlv> {
+    let mut out = Vec::with_capacity(val.data.len() + 1);
+    out.push(val.unused_bits);
+    out.extend_from_slice(&val.data);
+
+    let tlv = primitive_tlv(BIT_STRING_TAG, out);
+    decode_bit_string(&tlv)?;
+    Ok(tlv)
+}
+
+/// Decodes OCTET STRING
+pub fn decode_octet_string(tlv: &Tlv) -> Result<&[u8]> {
+    primitive(tlv, OCTET_STRING_TAG)
+}
+
+/// Encodes OCTET STRING
+pub fn encode_octet_string(val: &[u8]) -> Tlv {
+    primitive_tlv(OCTET_STRING_TAG, val.to_vec())
+}
+
+/// Checks NULL
+pub fn decode_null(tlv: &Tlv) -> Result<()> {
+    match primitive(tlv, NULL_TAG)? {
+        [] => Ok(()),
+        _ => Err(invalid(NULL_TAG)),
+    }
+}
+
+/// Encodes NULL
+pub fn encode_null() -> Tlv {
+    primitive_tlv(NULL_TAG, Vec::new())
+}
+
+/// Decodes OBJECT IDENTIFIER
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::Tlv;
+/// # use tlv_parser::asn1;
+/// // sha256WithRSAEncryption
+/// let tlv = Tlv::from_vec(&[
+///     0x06, 0x09, 0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B,
+/// ]).unwrap();
+/// assert_eq!(asn1::decode_oid(&tlv).unwrap().arcs(), [1, 2, 840, 113549, 1, 1, 11]);
+/// ```
+pub fn decode_oid(tlv: &Tlv) -> Result<Oid> {
+    Oid::from_der(primitive(tlv, OID_TAG)?)
+}
+
+/// Encodes OBJECT IDENTIFIER
+pub fn encode_oid(val: &Oid) -> Tlv {
+    val.to_tlv()
+}
+
+/// Decodes UTF8String
+pub fn decode_utf8_string(tlv: &Tlv) -> Result<&str> {
+    core::str::from_utf8(primitive(tlv, UTF8_STRING_TAG)?).map_err(|_| invalid(UTF8_STRING_TAG))
+}
+
+/// Encodes UTF8String
+pub fn encode_utf8_string(val: &str) -> Tlv {
+    primitive_tlv(UTF8_STRING_TAG, val.as_bytes().to_vec())
+}
+
+/// Decodes PrintableString
+pub fn decode_printable_string(tlv: &Tlv) -> Result<&str> {
+    decode_restricted(tlv, PRINTABLE_STRING_TAG, is_printable)
+}
+
+/// Encodes PrintableString
+pub fn encode_printable_string(val: &str) -> Result<Tlv> {
+    encode_restricted(val, PRINTABLE_STRING_TAG, is_printable)
+}
+
+/// Decodes IA5String
+pub fn decode_ia5_string(tlv: &Tlv) -> Result<&str> {
+    decode_restricted(tlv, IA5_STRING_TAG, |x| x.is_ascii())
+}
+
+/// Encodes IA5String
+pub fn encode_ia5_string(val: &str) -> Result<Tlv> {
+    encode_restricted(val, IA5_STRING_TAG, |x| x.is_ascii())
+}
+
+/// Date and time in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Creates date and time after range checks
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<DateTime> {
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            _ => 0,
+        };
+
+        if year > 9999 || day == 0 || day > days || hour > 23 || minute > 59 || second > 59 {
+            return Err(TlvError::InvalidDateTime);
+        }
+
+        Ok(DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+}
+
+/// Formats as `YYYY-MM-DD HH:MM:SS`
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Decodes UTCTime (`YYMMDDHHMMSSZ`); years 50-99 are mapped to 1950-1999
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::asn1;
+/// let tlv = asn1::encode_utc_time(&asn1::DateTime::new(2024, 2, 29, 23, 59, 0).unwrap()).unwrap();
+/// assert_eq!(tlv.to_vec()[2..], *b"240229235900Z");
+/// assert_eq!(asn1::decode_utc_time(&tlv).unwrap().to_string(), "2024-02-29 23:59:00");
+/// ```
+pub fn decode_utc_time(tlv: &Tlv) -> Result<DateTime> {
+    let val = primitive(tlv, UTC_TIME_TAG)?;
+    if val.len() != 13 {
+        return Err(invalid(UTC_TIME_TAG));
+    }
+
+    let yy = digits(val, 0, UTC_TIME_TAG)?;
+    let year = if yy >= 50 { 1900 + yy } else { 2000 + yy };
+    decode_time(val, 2, year, UTC_TIME_TAG)
+}
+
+/// Encodes UTCTime; only years 1950-2049 can be represented
+pub fn encode_utc_time(val: &DateTime) -> Result<Tlv> {
+    if !(1950..2050).contains(&val.year) {
+        return Err(invalid(UTC_TIME_TAG));
+    }
+    Ok(encode_time(UTC_TIME_TAG, val, 2))
+}
+
+/// Decodes GeneralizedTime (`YYYYMMDDHHMMSSZ`); fractional seconds are not supported
+pub fn decode_generalized_time(tlv: &Tlv) -> Result<DateTime> {
+    let val = primitive(tlv, GENERALIZED_TIME_TAG)?;
+    if val.len() != 15 {
+        return Err(invalid(GENERALIZED_TIME_TAG));
+    }
+
+    let year = digits(val, 0, GENERALIZED_TIME_TAG)? * 100 + digits(val, 2, GENERALIZED_TIME_TAG)?;
+    decode_time(val, 4, year, GENERALIZED_TIME_TAG)
+}
+
+/// Encodes GeneralizedTime
+pub fn encode_generalized_time(val: &DateTime) -> Tlv {
+    encode_time(GENERALIZED_TIME_TAG, val, 4)
+}
+
+/// Returns content octets of primitive data object with given tag
+fn primitive(tlv: &Tlv, tag: Tag) -> Result<&[u8]> {
+    if tlv.tag() != tag {
+        return Err(TlvError::InvalidTagNumber);
+    }
+
+    match *tlv.val() {
+        Value::Val(ref v) => Ok(v),
+        Value::Nothing => Ok(&[]),
+        Value::TlvList(_) => Err(TlvError::ValExpected { tag_number: tag }),
+        Value::Invalid { ref error, .. } => Err(error.clone()),
+    }
+}
+
+fn primitive_tlv(tag: Tag, val: Vec<u8>) -> Tlv {
+    Tlv::new(tag, Value::Val(val)).expect("universal tag is primitive")
+}
+
+fn invalid(tag: Tag) -> TlvError {
+    TlvError::InvalidValue { tag_number: tag }
+}
+
+fn is_printable(x: char) -> bool {
+    x.is_ascii_alphanumeric() || " '()+,-./:=?".contains(x)
+}
+
+fn decode_restricted(tlv: &Tlv, tag: Tag, allowed: fn(char) -> bool) -> Result<&str> {
+    let val = core::str::from_utf8(primitive(tlv, tag)?).map_err(|_| invalid(tag))?;
+    if !val.chars().all(allowed) {
+        return Err(invalid(tag));
+    }
+    Ok(val)
+}
+
+fn encode_restricted(val: &str, tag: Tag, allowed: fn(char) -> bool) -> Result<Tlv> {
+    if !val.chars().all(allowed) {
+        return Err(invalid(tag));
+    }
+    Ok(primitive_tlv(tag, val.as_bytes().to_vec()))
+}
+
+/// Returns number coded by two ASCII digits at given offset
+fn digits(val: &[u8], offset: usize, tag: Tag) -> Result<u16> {
+    match val[offset..offset + 2] {
+        [a @ b'0'..=b'9', b @ b'0'..=b'9'] => Ok((a - b'0') as u16 * 10 + (b - b'0') as u16),
+        _ => Err(invalid(tag)),
+    }
+}
+
+/// Decodes `MMDDHHMMSSZ` starting at given offset
+fn decode_time(val: &[u8], offset: usize, year: u16, tag: Tag) -> Result<DateTime> {
+    if val[val.len() - 1] != b'Z' {
+        return Err(invalid(tag));
+    }
+
+    let mut fields = [0u8; 5];
+    for (i, x) in fields.iter_mut().enumerate() {
+        *x = digits(val, offset + i * 2, tag)? as u8;
+    }
+
+    let [month, day, hour, minute, second] = fields;
+    DateTime::new(year, month, day, hour, minute, second).map_err(|_| invalid(tag))
+}
+
+fn encode_time(tag: Tag, val: &DateTime, year_digits: usize) -> Tlv {
+    let mut out = String::new();
+    let year = alloc::format!("{:04}", val.year);
+    out.push_str(&year[4 - year_digits..]);
+    out.push_str(&alloc::format!(
+        "{:02}{:02}{:02}{:02}{:02}Z",
+        val.month,
+        val.day,
+        val.hour,
+        val.minute,
+        val.second
+    ));
+
+    primitive_tlv(tag, out.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn tlv(data: &[u8]) -> Tlv {
+        Tlv::from_vec(data).unwrap()
+    }
+
+    #[test]
+    fn integer_test() {
+        for x in [0, 1, -1, 127, 128, -128, -129, 255, 256, i64::MIN, i64::MAX] {
+            assert_eq!(decode_i64(&encode_i64(x)).unwrap(), x);
+        }
+
+        assert_eq!(encode_i64(0).to_vec(), [0x02, 0x01, 0x00]);
+        assert_eq!(encode_i64(128).to_vec(), [0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(encode_i64(-128).to_vec(), [0x02, 0x01, 0x80]);
+
+        // non-minimal encodings
+        assert!(decode_i64(&tlv(&[0x02, 0x02, 0x00, 0x7F])).is_err());
+        assert!(decode_i64(&tlv(&[0x02, 0x02, 0xFF, 0x80])).is_err());
+        assert!(decode_i64(&tlv(&[0x02, 0x00])).is_err());
+        assert!(decode_i64(&tlv(&[0x04, 0x01, 0x00])).is_err());
+
+        // too big for i64
+        let big = encode_unsigned(&[0xFF; 8]);
+        assert_eq!(big.to_vec()[..4], [0x02, 0x09, 0x00, 0xFF]);
+        assert_eq!(decode_integer_bytes(&big).unwrap().len(), 9);
+        assert!(decode_i64(&big).is_err());
+
+        assert_eq!(
+            encode_unsigned(&[0x00, 0x00, 0x01]).to_vec(),
+            [0x02, 0x01, 0x01]
+        );
+        assert_eq!(encode_unsigned(&[]).to_vec(), [0x02, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn bit_string_test() {
+        let bits = decode_bit_string(&tlv(&[0x03, 0x03, 0x06, 0x6E, 0x40])).unwrap();
+        assert_eq!(bits.unused_bits, 6);
+        assert_eq!(bits.bit_len(), 10);
+        assert_eq!(
+            encode_bit_string(&bits).unwrap().to_vec(),
+            [0x03, 0x03, 0x06, 0x6E, 0x40]
+        );
+
+        // unused bits are not zero
+        assert!(decode_bit_string(&tlv(&[0x03, 0x03, 0x06, 0x6E, 0x41])).is_err());
+        assert!(decode_bit_string(&tlv(&[0x03, 0x01, 0x01])).is_err());
+        assert!(decode_bit_string(&tlv(&[0x03, 0x00])).is_err());
+        assert!(encode_bit_string(&BitString {
+            unused_bits: 8,
+            data: vec![0x00]
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn oid_test() {
+        let oid = decode_oid(&tlv(&[0x06, 0x03, 0x55, 0x04, 0x03])).unwrap();
+        assert_eq!(oid.arcs(), [2, 5, 4, 3]);
+        assert_eq!(encode_oid(&oid).to_vec(), [0x06, 0x03, 0x55, 0x04, 0x03]);
+
+        assert!(decode_oid(&tlv(&[0x06, 0x03, 0x55, 0x80, 0x03])).is_err());
+        assert!(decode_oid(&tlv(&[0x06, 0x00])).is_err());
+    }
+
+    #[test]
+    fn string_test() {
+        assert_eq!(
+            decode_utf8_string(&encode_utf8_string("Zürich")).unwrap(),
+            "Zürich"
+        );
+        assert!(decode_utf8_string(&tlv(&[0x0C, 0x01, 0xFF])).is_err());
+
+        let s = encode_printable_string("Test CA (1)").unwrap();
+        assert_eq!(decode_printable_string(&s).unwrap(), "Test CA (1)");
+        assert!(encode_printable_string("a@b").is_err());
+        assert!(decode_printable_string(&tlv(&[0x13, 0x01, b'*'])).is_err());
+
+        let s = encode_ia5_string("a@b.com").unwrap();
+        assert_eq!(decode_ia5_string(&s).unwrap(), "a@b.com");
+        assert!(encode_ia5_string("ü").is_err());
+
+        assert_eq!(
+            decode_octet_string(&encode_octet_string(&[1, 2])).unwrap(),
+            [1, 2]
+        );
+        assert!(decode_bool(&encode_bool(false)).is_ok_and(|x| !x));
+        assert!(decode_null(&encode_null()).is_ok());
+        assert!(decode_null(&tlv(&[0x05, 0x01, 0x00])).is_err());
+    }
+
+    #[test]
+    fn time_test() {
+        let t = decode_utc_time(&tlv(b"\x17\x0D491231235959Z")).unwrap();
+        assert_eq!(t, DateTime::new(2049, 12, 31, 23, 59, 59).unwrap());
+        let t = decode_utc_time(&tlv(b"\x17\x0D500101000000Z")).unwrap();
+        assert_eq!(t.year, 1950);
+
+        assert!(decode_utc_time(&tlv(b"\x17\x0D230229000000Z")).is_err());
+        assert!(decode_utc_time(&tlv(b"\x17\x0B2301010000Z")).is_err());
+        assert!(decode_utc_time(&tlv(b"\x17\x11230101000000+0100")).is_err());
+        assert!(encode_utc_time(&DateTime::new(2050, 1, 1, 0, 0, 0).unwrap()).is_err());
+        assert_eq!(
+            DateTime::new(2023, 2, 29, 0, 0, 0),
+            Err(TlvError::InvalidDateTime)
+        );
+
+        let t = DateTime::new(2050, 1, 1, 0, 0, 0).unwrap();
+        let tlv = encode_generalized_time(&t);
+        assert_eq!(tlv.to_vec()[2..], *b"20500101000000Z");
+        assert_eq!(decode_generalized_time(&tlv).unwrap(), t);
+        assert!(t > DateTime::new(2049, 12, 31, 23, 59, 59).unwrap());
+    }
+}
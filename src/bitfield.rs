@@ -0,0 +1,328 @@
+//! Typed wrappers for EMV data elements which are bitmaps.
+//!
+//! Meaning of bits follows EMV 4.3 Book 3 Annex C, EMV Contactless Book C-2
+//! (AIP byte 2) and Visa Contactless Payment Specification (CTQ and TTQ).
+
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::tlv::{Tag, Tlv, Value};
+use crate::TlvError;
+
+macro_rules! bitfield {
+    (
+        $(#[$meta:meta])*
+        $name:ident, $tag:expr, $len:expr;
+        $( $byte:literal, $mask:literal, $get:ident, $set:ident, $desc:literal; )*
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $name([u8; $len]);
+
+        impl $name {
+            /// Tag number of data object
+            pub const TAG: Tag = $tag;
+
+            /// Length of data object value in bytes
+            pub const LEN: usize = $len;
+
+            const BITS: &'static [(usize, u8, &'static str)] = &[$(($byte - 1, $mask, $desc)),*];
+
+            /// Creates value with all bits cleared
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Returns value as array of bytes
+            pub fn bytes(&self) -> [u8; $len] {
+                self.0
+            }
+
+            /// Returns primitive TLV with this value
+            pub fn to_tlv(&self) -> Tlv {
+                Tlv::new(Self::TAG, Value::Val(self.0.to_vec()))
+                    .expect("bitfield tags are primitive")
+            }
+
+            /// Returns descriptions of all set bits
+            pub fn set_bits(&self) -> impl Iterator<Item = &'static str> + '_ {
+                Self::BITS
+                    .iter()
+                    .filter(move |&&(byte, mask, _)| self.0[byte] & mask != 0)
+                    .map(|&(_, _, desc)| desc)
+            }
+
+            $(
+                #[doc = concat!("Returns true if set: ", $desc)]
+                pub fn $get(&self) -> bool {
+                    self.0[$byte - 1] & $mask != 0
+                }
+
+                #[doc = concat!("Sets or clears: ", $desc)]
+                pub fn $set(&mut self, value: bool) {
+                    if value {
+                        self.0[$byte - 1] |= $mask;
+                    } else {
+                        self.0[$byte - 1] &= !$mask;
+                    }
+                }
+            )*
+        }
+
+        impl From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                $name(bytes)
+            }
+        }
+
+        impl From<$name> for Value {
+            fn from(val: $name) -> Value {
+                Value::Val(val.0.to_vec())
+            }
+        }
+
+        impl TryFrom<&[u8]> for $name {
+            type Error = TlvError;
+
+            fn try_from(bytes: &[u8]) -> Result<Self, TlvError> {
+                <[u8; $len]>::try_from(bytes)
+                    .map($name)
+                    .map_err(|_| TlvError::InvalidValue {
+                        tag_number: Self::TAG,
+                    })
+            }
+        }
+
+        impl TryFrom<&Value> for $name {
+            type Error = TlvError;
+
+            fn try_from(value: &Value) -> Result<Self, TlvError> {
+                match *value {
+                    Value::Val(ref v) => $name::try_from(v.as_slice()),
+                    _ => Err(TlvError::ValExpected {
+                        tag_number: Self::TAG,
+                    }),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                for x in self.0.iter() {
+                    write!(f, "{:02X}", x)?;
+                }
+
+                let mut sep = ": ";
+                for desc in self.set_bits() {
+                    write!(f, "{}{}", sep, desc)?;
+                    sep = ", ";
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+bitfield! {
+    /// Terminal Verification Results (`95`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::bitfield::Tvr;
+    /// let mut tvr = Tvr::from([0x80, 0x00, 0x00, 0x80, 0x00]);
+    /// assert!(tvr.offline_data_authentication_not_performed());
+    /// assert!(tvr.transaction_exceeds_floor_limit());
+    ///
+    /// tvr.set_transaction_exceeds_floor_limit(false);
+    /// assert_eq!(
+    ///     tvr.to_string(),
+    ///     "8000000000: Offline data authentication was not performed"
+    /// );
+    /// ```
+    Tvr, 0x95, 5;
+    1, 0x80, offline_data_authentication_not_performed, set_offline_data_authentication_not_performed, "Offline data authentication was not performed";
+    1, 0x40, sda_failed, set_sda_failed, "SDA failed";
+    1, 0x20, icc_data_missing, set_icc_data_missing, "ICC data missing";
+    1, 0x10, card_on_exception_file, set_card_on_exception_file, "Card appears on terminal exception file";
+    1, 0x08, dda_failed, set_dda_failed, "DDA failed";
+    1, 0x04, cda_failed, set_cda_failed, "CDA failed";
+    1, 0x02, sda_selected, set_sda_selected, "SDA selected";
+    2, 0x80, different_application_versions, set_different_application_versions, "ICC and terminal have different application versions";
+    2, 0x40, expired_application, set_expired_application, "Expired application";
+    2, 0x20, application_not_yet_effective, set_application_not_yet_effective, "Application not yet effective";
+    2, 0x10, service_not_allowed, set_service_not_allowed, "Requested service not allowed for card product";
+    2, 0x08, new_card, set_new_card, "New card";
+    3, 0x80, cardholder_verification_not_successful, set_cardholder_verification_not_successful, "Cardholder verification was not successful";
+    3, 0x40, unrecognised_cvm, set_unrecognised_cvm, "Unrecognised CVM";
+    3, 0x20, pin_try_limit_exceeded, set_pin_try_limit_exceeded, "PIN Try Limit exceeded";
+    3, 0x10, pin_pad_not_present, set_pin_pad_not_present, "PIN entry required and PIN pad not present or not working";
+    3, 0x08, pin_not_entered, set_pin_not_entered, "PIN entry required, PIN pad present, but PIN was not entered";
+    3, 0x04, online_pin_entered, set_online_pin_entered, "Online PIN entered";
+    4, 0x80, transaction_exceeds_floor_limit, set_transaction_exceeds_floor_limit, "Transaction exceeds floor limit";
+    4, 0x40, lower_offline_limit_exceeded, set_lower_offline_limit_exceeded, "Lower consecutive offline limit exceeded";
+    4, 0x20, upper_offline_limit_exceeded, set_upper_offline_limit_exceeded, "Upper consecutive offline limit exceeded";
+    4, 0x10, selected_randomly_for_online, set_selected_randomly_for_online, "Transaction selected randomly for online processing";
+    4, 0x08, merchant_forced_online, set_merchant_forced_online, "Merchant forced transaction online";
+    5, 0x80, default_tdol_used, set_default_tdol_used, "Default TDOL used";
+    5, 0x40, issuer_authentication_failed, set_issuer_authentication_failed, "Issuer authentication failed";
+    5, 0x20, script_failed_before_final_gen_ac, set_script_failed_before_final_gen_ac, "Script processing failed before final GENERATE AC";
+    5, 0x10, script_failed_after_final_gen_ac, set_script_failed_after_final_gen_ac, "Script processing failed after final GENERATE AC";
+}
+
+bitfield! {
+    /// Transaction Status Information (`9B`)
+    Tsi, 0x9B, 2;
+    1, 0x80, oda_performed, set_oda_performed, "Offline data authentication was performed";
+    1, 0x40, cardholder_verification_performed, set_cardholder_verification_performed, "Cardholder verification was performed";
+    1, 0x20, card_risk_management_performed, set_card_risk_management_performed, "Card risk management was performed";
+    1, 0x10, issuer_authentication_performed, set_issuer_authentication_performed, "Issuer authentication was performed";
+    1, 0x08, terminal_risk_management_performed, set_terminal_risk_management_performed, "Terminal risk management was performed";
+    1, 0x04, script_processing_performed, set_script_processing_performed, "Script processing was performed";
+}
+
+bitfield! {
+    /// Application Interchange Profile (`82`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::bitfield::Aip;
+    /// let aip = Aip::from([0x5D, 0x80]);
+    /// assert!(aip.sda_supported());
+    /// assert!(aip.cda_supported());
+    /// assert!(!aip.dda_supported());
+    /// ```
+    Aip, 0x82, 2;
+    1, 0x40, sda_supported, set_sda_supported, "SDA supported";
+    1, 0x20, dda_supported, set_dda_supported, "DDA supported";
+    1, 0x10, cardholder_verification_supported, set_cardholder_verification_supported, "Cardholder verification is supported";
+    1, 0x08, terminal_risk_management_required, set_terminal_risk_management_required, "Terminal risk management is to be performed";
+    1, 0x04, issuer_authentication_supported, set_issuer_authentication_supported, "Issuer authentication is supported";
+    1, 0x02, on_device_cvm_supported, set_on_device_cvm_supported, "On device cardholder verification is supported";
+    1, 0x01, cda_supported, set_cda_supported, "CDA supported";
+    2, 0x80, emv_mode_supported, set_emv_mode_supported, "EMV mode is supported";
+    2, 0x01, relay_resistance_protocol_supported, set_relay_resistance_protocol_supported, "Relay resistance protocol is supported";
+}
+
+bitfield! {
+    /// Application Usage Control (`9F07`)
+    Auc, 0x9F07, 2;
+    1, 0x80, domestic_cash, set_domestic_cash, "Valid for domestic cash transactions";
+    1, 0x40, international_cash, set_international_cash, "Valid for international cash transactions";
+    1, 0x20, domestic_goods, set_domestic_goods, "Valid for domestic goods";
+    1, 0x10, international_goods, set_international_goods, "Valid for international goods";
+    1, 0x08, domestic_services, set_domestic_services, "Valid for domestic services";
+    1, 0x04, international_services, set_international_services, "Valid for international services";
+    1, 0x02, atms, set_atms, "Valid at ATMs";
+    1, 0x01, terminals_other_than_atms, set_terminals_other_than_atms, "Valid at terminals other than ATMs";
+    2, 0x80, domestic_cashback, set_domestic_cashback, "Domestic cashback allowed";
+    2, 0x40, international_cashback, set_international_cashback, "International cashback allowed";
+}
+
+bitfield! {
+    /// Card Transaction Qualifiers (`9F6C`)
+    Ctq, 0x9F6C, 2;
+    1, 0x80, online_pin_required, set_online_pin_required, "Online PIN required";
+    1, 0x40, signature_required, set_signature_required, "Signature required";
+    1, 0x20, online_if_oda_fails, set_online_if_oda_fails, "Go online if offline data authentication fails and reader is online capable";
+    1, 0x10, switch_interface_if_oda_fails, set_switch_interface_if_oda_fails, "Switch interface if offline data authentication fails and reader supports contact chip";
+    1, 0x08, online_if_application_expired, set_online_if_application_expired, "Go online if application expired";
+    1, 0x04, switch_interface_for_cash, set_switch_interface_for_cash, "Switch interface for cash transactions";
+    1, 0x02, switch_interface_for_cashback, set_switch_interface_for_cashback, "Switch interface for cashback transactions";
+    1, 0x01, not_valid_for_atm, set_not_valid_for_atm, "Not valid for contactless ATM transactions";
+    2, 0x80, consumer_device_cvm_performed, set_consumer_device_cvm_performed, "Consumer device CVM performed";
+    2, 0x40, issuer_update_supported, set_issuer_update_supported, "Card supports issuer update processing at the POS";
+}
+
+bitfield! {
+    /// Terminal Transaction Qualifiers (`9F66`)
+    Ttq, 0x9F66, 4;
+    1, 0x80, mag_stripe_mode_supported, set_mag_stripe_mode_supported, "Mag-stripe mode supported";
+    1, 0x20, emv_mode_supported, set_emv_mode_supported, "EMV mode supported";
+    1, 0x10, emv_contact_chip_supported, set_emv_contact_chip_supported, "EMV contact chip supported";
+    1, 0x08, offline_only_reader, set_offline_only_reader, "Offline-only reader";
+    1, 0x04, online_pin_supported, set_online_pin_supported, "Online PIN supported";
+    1, 0x02, signature_supported, set_signature_supported, "Signature supported";
+    1, 0x01, oda_for_online_supported, set_oda_for_online_supported, "Offline data authentication for online authorizations supported";
+    2, 0x80, online_cryptogram_required, set_online_cryptogram_required, "Online cryptogram required";
+    2, 0x40, cvm_required, set_cvm_required, "CVM required";
+    2, 0x20, offline_pin_supported, set_offline_pin_supported, "Contact chip offline PIN supported";
+    3, 0x80, issuer_update_supported, set_issuer_update_supported, "Issuer update processing supported";
+    3, 0x40, consumer_device_cvm_supported, set_consumer_device_cvm_supported, "Consumer device CVM supported";
+}
+
+bitfield! {
+    /// Terminal Capabilities (`9F33`)
+    TerminalCapabilities, 0x9F33, 3;
+    1, 0x80, manual_key_entry, set_manual_key_entry, "Manual key entry";
+    1, 0x40, magnetic_stripe, set_magnetic_stripe, "Magnetic stripe";
+    1, 0x20, ic_with_contacts, set_ic_with_contacts, "IC with contacts";
+    2, 0x80, plaintext_pin, set_plaintext_pin, "Plaintext PIN for ICC verification";
+    2, 0x40, enciphered_pin_online, set_enciphered_pin_online, "Enciphered PIN for online verification";
+    2, 0x20, signature, set_signature, "Signature (paper)";
+    2, 0x10, enciphered_pin_offline, set_enciphered_pin_offline, "Enciphered PIN for offline verification";
+    2, 0x08, no_cvm_required, set_no_cvm_required, "No CVM required";
+    3, 0x80, sda, set_sda, "SDA";
+    3, 0x40, dda, set_dda, "DDA";
+    3, 0x20, card_capture, set_card_capture, "Card capture";
+    3, 0x08, cda, set_cda, "CDA";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn tvr_test() {
+        let mut tvr = Tvr::new();
+        assert_eq!(tvr.to_string(), "0000000000");
+
+        tvr.set_cda_failed(true);
+        tvr.set_new_card(true);
+        tvr.set_script_failed_after_final_gen_ac(true);
+        assert_eq!(tvr.bytes(), [0x04, 0x08, 0x00, 0x00, 0x10]);
+        assert_eq!(
+            tvr.set_bits().collect::<Vec<_>>(),
+            vec![
+                "CDA failed",
+                "New card",
+                "Script processing failed after final GENERATE AC"
+            ]
+        );
+
+        tvr.set_new_card(false);
+        assert!(!tvr.new_card());
+        assert_eq!(
+            tvr.to_tlv().to_vec(),
+            [0x95, 0x05, 0x04, 0x00, 0x00, 0x00, 0x10]
+        );
+    }
+
+    #[test]
+    fn value_conversion_test() {
+        let tsi = Tsi::try_from(&Value::Val(vec![0xE8, 0x00])).unwrap();
+        assert!(tsi.oda_performed());
+        assert!(tsi.terminal_risk_management_performed());
+        assert!(!tsi.issuer_authentication_performed());
+        assert_eq!(Value::from(tsi), Value::Val(vec![0xE8, 0x00]));
+
+        assert!(Ttq::try_from(&Value::Val(vec![0x36, 0x00, 0x00])).is_err());
+        assert!(Ctq::try_from(&Value::Nothing).is_err());
+
+        let caps = TerminalCapabilities::try_from(&[0xE0, 0xF8, 0xC8][..]).unwrap();
+        assert!(caps.cda());
+        assert!(!caps.card_capture());
+        assert_eq!(
+            caps.to_string(),
+            "E0F8C8: Manual key entry, Magnetic stripe, IC with contacts, \
+             Plaintext PIN for ICC verification, Enciphered PIN for online verification, \
+             Signature (paper), Enciphered PIN for offline verification, No CVM required, \
+             SDA, DDA, CDA"
+        );
+    }
+}
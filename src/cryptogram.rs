@@ -0,0 +1,453 @@
+//! Application Cryptogram (ARQC, TC, AAC) and ARPC computation.
+//!
+//! Implements ICC master key derivation (option A), session key derivation, MAC computation
+//! and ARPC methods 1 and 2 as defined in EMV 4.3 Book 2, Annex A1 and section 8.2.
+//! Mastercard proprietary session key derivation (SKD) is supported too.
+//! Available with `cryptogram` feature.
+
+use alloc::vec::Vec;
+
+use aes::Aes128;
+use des::cipher::generic_array::GenericArray;
+use des::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use des::{Des, TdesEde2};
+
+use crate::dol::{Dol, DolSource};
+use crate::tlv::Tlv;
+use crate::Result;
+
+/// Block cipher used for cryptogram computation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Double-length Triple DES with ISO 9797-1 MAC algorithm 3
+    Tdes,
+    /// AES-128 with CMAC
+    Aes,
+}
+
+impl Algorithm {
+    /// Returns cipher block length in bytes
+    pub fn block_len(self) -> usize {
+        match self {
+            Algorithm::Tdes => 8,
+            Algorithm::Aes => 16,
+        }
+    }
+
+    /// Encrypts single block in place
+    fn encrypt(self, key: &[u8; 16], block: &mut [u8]) {
+        match self {
+            Algorithm::Tdes => TdesEde2::new(GenericArray::from_slice(key))
+                .encrypt_block(GenericArray::from_mut_slice(block)),
+            Algorithm::Aes => Aes128::new(GenericArray::from_slice(key))
+                .encrypt_block(GenericArray::from_mut_slice(block)),
+        }
+    }
+}
+
+/// Builds CDOL1 or CDOL2 related data for GENERATE AC command
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::{Tlv, Value};
+/// # use tlv_parser::cryptogram::cdol_data;
+/// let cdol = Tlv::new(0x8C, Value::Val(vec![0x9F, 0x02, 0x06, 0x9F, 0x37, 0x04])).unwrap();
+/// let source = [
+///     Tlv::new(0x9F02, Value::Val(vec![0x00, 0x00, 0x00, 0x00, 0x01, 0x00])).unwrap(),
+///     Tlv::new(0x9F37, Value::Val(vec![0x11, 0x22, 0x33, 0x44])).unwrap(),
+/// ];
+/// assert_eq!(
+///     cdol_data(&cdol, &source[..]).unwrap(),
+///     [0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x11, 0x22, 0x33, 0x44]
+/// );
+/// ```
+pub fn cdol_data<S: DolSource + ?Sized>(cdol: &Tlv, source: &S) -> Result<Vec<u8>> {
+    Ok(Dol::from_tlv(cdol)?.build(source))
+}
+
+/// Derives ICC Triple DES master key from Issuer Master Key using option A
+///
+/// `pan` is Application PAN (`5A`) in compressed numeric format, `psn` is
+/// PAN Sequence Number (`5F34`).
+pub fn derive_icc_master_key(imk: &[u8; 16], pan: &[u8], psn: u8) -> [u8; 16] {
+    let mut digits: Vec<u8> = pan
+        .iter()
+        .flat_map(|x| [x >> 4, x & 0x0F])
+        .take_while(|&x| x != 0x0F)
+        .collect();
+    digits.extend_from_slice(&[psn >> 4, psn & 0x0F]);
+
+    let mut y = [0u8; 8];
+    for (i, d) in digits.iter().rev().take(16).enumerate() {
+        y[7 - i / 2] |= d << (4 * (i % 2));
+    }
+
+    let mut key = [0; 16];
+    key[..8].copy_from_slice(&y);
+    key[8..].copy_from_slice(&y.map(|x| x ^ 0xFF));
+    Algorithm::Tdes.encrypt(imk, &mut key[..8]);
+    Algorithm::Tdes.encrypt(imk, &mut key[8..]);
+
+    adjust_parity(&mut key);
+    key
+}
+
+/// Derives session key using EMV common session key derivation
+pub fn derive_session_key(algorithm: Algorithm, master_key: &[u8; 16], atc: u16) -> [u8; 16] {
+    let mut key = [0; 16];
+    let diversify = |block: &mut [u8], marker| {
+        block[..2].copy_from_slice(&atc.to_be_bytes());
+        block[2] = marker;
+        algorithm.encrypt(master_key, block);
+    };
+
+    match algorithm {
+        Algorithm::Tdes => {
+            diversify(&mut key[..8], 0xF0);
+            diversify(&mut key[8..], 0x0F);
+        }
+        Algorithm::Aes => diversify(&mut key, 0xF0),
+    }
+
+    key
+}
+
+/// Derives Triple DES session key using Mastercard proprietary SKD
+///
+/// `unpredictable_number` is Unpredictable Number (`9F37`) sent to card.
+pub fn derive_mastercard_session_key(
+    master_key: &[u8; 16],
+    atc: u16,
+    unpredictable_number: &[u8; 4],
+) -> [u8; 16] {
+    let mut key = [0; 16];
+    for (block, marker) in key.chunks_mut(8).zip([0xF0, 0x0F]) {
+        block[..2].copy_from_slice(&atc.to_be_bytes());
+        block[2] = marker;
+        block[4..].copy_from_slice(unpredictable_number);
+        Algorithm::Tdes.encrypt(master_key, block);
+    }
+
+    key
+}
+
+/// Computes 8-byte MAC over data
+///
+/// Triple DES uses ISO 9797-1 MAC algorithm 3 with padding method 2,
+/// AES uses CMAC truncated to 8 bytes.
+pub fn mac(algorithm: Algorithm, key: &[u8; 16], data: &[u8]) -> [u8; 8] {
+    let full = match algorithm {
+        Algorithm::Tdes => retail_mac(key, data),
+        Algorithm::Aes => cmac(key, data),
+    };
+
+    let mut out = [0; 8];
+    out.copy_from_slice(&full[..8]);
+    out
+}
+
+/// Computes Application Cryptogram over CDOL related data and other transaction data
+pub fn compute_ac(algorithm: Algorithm, session_key: &[u8; 16], data: &[u8]) -> [u8; 8] {
+    mac(algorithm, session_key, data)
+}
+
+/// Checks Application Cryptogram returned by card
+///
+/// Comparison takes the same time regardless of position of the first mismatching byte.
+pub fn verify_ac(algorithm: Algorithm, session_key: &[u8; 16], data: &[u8], ac: &[u8]) -> bool {
+    let expected = compute_ac(algorithm, session_key, data);
+    if ac.len() != expected.len() {
+        return false;
+    }
+
+    let diff = expected.iter().zip(ac).fold(0, |acc, (x, y)| acc | (x ^ y));
+    core::hint::black_box(diff) == 0
+}
+
+/// Computes ARPC using method 1
+///
+/// ARQC is XOR-ed with Authorisation Response Code (`8A`) and encrypted;
+/// for AES the block is padded with zeros and the result is truncated to 8 bytes.
+pub fn arpc_method1(
+    algorithm: Algorithm,
+    session_key: &[u8; 16],
+    arqc: &[u8; 8],
+    arc: &[u8; 2],
+) -> [u8; 8] {
+    let mut block = [0; 16];
+    block[..8].copy_from_slice(arqc);
+    block[0] ^= arc[0];
+    block[1] ^= arc[1];
+
+    algorithm.encrypt(session_key, &mut block[..algorithm.block_len()]);
+
+    let mut out = [0; 8];
+    out.copy_from_slice(&block[..8]);
+    out
+}
+
+/// Computes ARPC using method 2
+///
+/// `csu` is Card Status Update, `proprietary_data` is optional Proprietary
+/// Authentication Data (up to 8 bytes).
+pub fn arpc_method2(
+    algorithm: Algorithm,
+    session_key: &[u8; 16],
+    arqc: &[u8; 8],
+    csu: &[u8; 4],
+    proprietary_data: &[u8],
+) -> [u8; 4] {
+    let mut data = Vec::with_capacity(12 + proprietary_data.len());
+    data.extend_from_slice(arqc);
+    data.extend_from_slice(csu);
+    data.extend_from_slice(proprietary_data);
+
+    let mut out = [0; 4];
+    out.copy_from_slice(&mac(algorithm, session_key, &data)[..4]);
+    out
+}
+
+/// ISO 9797-1 MAC algorithm 3 with padding method 2
+fn retail_mac(key: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let left = Des::new(GenericArray::from_slice(&key[..8]));
+    let right = Des::new(GenericArray::from_slice(&key[8..]));
+
+    let mut padded = data.to_vec();
+    padded.push(0x80);
+    padded.resize(padded.len().div_ceil(8) * 8, 0x00);
+
+    let mut state = [0; 8];
+    for chunk in padded.chunks(8) {
+        state.iter_mut().zip(chunk).for_each(|(s, x)| *s ^= x);
+        left.encrypt_block(GenericArray::from_mut_slice(&mut state));
+    }
+
+    right.decrypt_block(GenericArray::from_mut_slice(&mut state));
+    left.encrypt_block(GenericArray::from_mut_slice(&mut state));
+    state.to_vec()
+}
+
+/// AES-CMAC as defined in NIST SP 800-38B
+fn cmac(key: &[u8; 16], data: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new(GenericArray::from_slice(key));
+
+    let subkey = |block: &[u8; 16]| {
+        let mut out = [0; 16];
+        for i in 0..16 {
+            out[i] = block[i] << 1 | block.get(i + 1).map_or(0, |x| x >> 7);
+        }
+        if block[0] & 0x80 != 0 {
+            out[15] ^= 0x87;
+        }
+        out
+    };
+
+    let mut l = [0; 16];
+    cipher.encrypt_block(GenericArray::from_mut_slice(&mut l));
+    let k1 = subkey(&l);
+    let k2 = subkey(&k1);
+
+    let blocks = data.len().div_ceil(16).max(1);
+    let complete = !data.is_empty() && data.len() % 16 == 0;
+
+    let mut last = [0; 16];
+    let tail = &data[(blocks - 1) * 16..];
+    last[..tail.len()].copy_from_slice(tail);
+    if complete {
+        last.iter_mut().zip(k1).for_each(|(x, k)| *x ^= k);
+    } else {
+        last[tail.len()] = 0x80;
+        last.iter_mut().zip(k2).for_each(|(x, k)| *x ^= k);
+    }
+
+    let mut state = [0; 16];
+    for chunk in data.chunks(16).take(blocks - 1).chain([&last[..]]) {
+        state.iter_mut().zip(chunk).for_each(|(s, x)| *s ^= x);
+        cipher.encrypt_block(GenericArray::from_mut_slice(&mut state));
+    }
+
+    state.to_vec()
+}
+
+/// Sets odd parity of each byte of DES key
+fn adjust_parity(key: &mut [u8]) {
+    for x in key {
+        if x.count_ones() % 2 == 0 {
+            *x ^= 0x01;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn key(s: &str) -> [u8; 16] {
+        hex(s).try_into().unwrap()
+    }
+
+    #[test]
+    fn cmac_test() {
+        // RFC 4493, section 4
+        let key = key("2B7E151628AED2A6ABF7158809CF4F3C");
+        let msg = hex(concat!(
+            "6BC1BEE22E409F96E93D7E117393172AAE2D8A571E03AC9C9EB76FAC45AF8E51",
+            "30C81C46A35CE411E5FBC1191A0A52EFF69F2445DF4F9B17AD2B417BE66C3710"
+        ));
+
+        assert_eq!(cmac(&key, &[]), hex("BB1D6929E95937287FA37D129B756746"));
+        assert_eq!(
+            cmac(&key, &msg[..16]),
+            hex("070A16B46B4D4144F79BDD9DD04A287C")
+        );
+        assert_eq!(
+            cmac(&key, &msg[..40]),
+            hex("DFA66747DE9AE63030CA32611497C827")
+        );
+        assert_eq!(cmac(&key, &msg), hex("51F0BEBF7E3B9D92FC49741779363CFE"));
+    }
+
+    fn tdes(key: &[u8; 16], block: &[u8]) -> Vec<u8> {
+        let mut block = block.to_vec();
+        Algorithm::Tdes.encrypt(key, &mut block);
+        block
+    }
+
+    /// ISO 9797-1 MAC algorithm 3 without padding: single DES CBC with the left key
+    /// half and Triple DES for the last block
+    fn unpadded_retail_mac(key: &[u8; 16], data: &[u8]) -> Vec<u8> {
+        let mut left = [0; 16];
+        left[..8].copy_from_slice(&key[..8]);
+        left[8..].copy_from_slice(&key[..8]);
+
+        let blocks = data.len() / 8;
+        let mut state = [0; 8];
+        for (i, chunk) in data.chunks(8).enumerate() {
+            state.iter_mut().zip(chunk).for_each(|(s, x)| *s ^= x);
+            let key = if i + 1 == blocks { key } else { &left };
+            Algorithm::Tdes.encrypt(key, &mut state);
+        }
+        state.to_vec()
+    }
+
+    #[test]
+    fn cipher_test() {
+        // FIPS 81, appendix B: DES ECB of "Now is t" (double-length key with equal halves)
+        assert_eq!(
+            tdes(&key("0123456789ABCDEF0123456789ABCDEF"), b"Now is t"),
+            hex("3FA40E8A984D4815")
+        );
+
+        // FIPS 197, appendix C.1
+        let mut block = hex("00112233445566778899AABBCCDDEEFF");
+        Algorithm::Aes.encrypt(&key("000102030405060708090A0B0C0D0E0F"), &mut block);
+        assert_eq!(block, hex("69C4E0D86A7B0430D8CDB78070B4C55A"));
+    }
+
+    #[test]
+    fn retail_mac_test() {
+        // ANSI X9.19 retail MAC example
+        let key = key("0123456789ABCDEFFEDCBA9876543210");
+        let msg = b"Now is the time for all ";
+        assert_eq!(unpadded_retail_mac(&key, msg), hex("A1C72E74EA3FA9B6"));
+
+        // padding method 2 appends '80' and zeros up to block boundary
+        let mut padded = msg.to_vec();
+        padded.extend_from_slice(&[0x80, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(retail_mac(&key, msg), unpadded_retail_mac(&key, &padded));
+
+        let mut padded = msg[..20].to_vec();
+        padded.extend_from_slice(&[0x80, 0, 0, 0]);
+        assert_eq!(
+            retail_mac(&key, &msg[..20]),
+            unpadded_retail_mac(&key, &padded)
+        );
+    }
+
+    #[test]
+    fn icc_master_key_test() {
+        // pyemv documentation, `kd.derive_icc_mk_a` example:
+        // PAN 99012345678901234, PSN 45
+        let imk = key("0123456789ABCDEFFEDCBA9876543210");
+        let pan = [0x99, 0x01, 0x23, 0x45, 0x67, 0x89, 0x01, 0x23, 0x4F];
+        assert_eq!(
+            derive_icc_master_key(&imk, &pan, 0x45),
+            key("67F8292358083E5EA7AB7FDA58D53B6B")
+        );
+    }
+
+    // No published EMV-level vectors are available for the inputs below; the known
+    // answers were computed with an independent implementation (Python `cryptography`
+    // package) of EMV 4.3 Book 2, A1.3 and 8.2, and of M/Chip session key derivation
+
+    const MK: &str = "67F8292358083E5EA7AB7FDA58D53B6B";
+    const DATA: &str = "000000001000000000000000084000800080000840240101003011223344580000002A";
+    const CSU: [u8; 4] = [0x00, 0x82, 0x00, 0x00];
+
+    #[test]
+    fn session_key_test() {
+        let mk = key(MK);
+
+        assert_eq!(
+            derive_session_key(Algorithm::Tdes, &mk, 0x002A),
+            key("38179C63C74BE8930BDCD1DA964D2BB4")
+        );
+        assert_eq!(
+            derive_mastercard_session_key(&mk, 0x002A, &[0x11, 0x22, 0x33, 0x44]),
+            key("3786AE82C63ABF14B43DEBCA2996DA36")
+        );
+        assert_eq!(
+            derive_session_key(Algorithm::Aes, &mk, 0x002A),
+            key("71D6F31A90B363ECC6B4AA2EE21AA365")
+        );
+    }
+
+    #[test]
+    fn tdes_test() {
+        let sk = key("38179C63C74BE8930BDCD1DA964D2BB4");
+        let data = hex(DATA);
+
+        let arqc = compute_ac(Algorithm::Tdes, &sk, &data);
+        assert_eq!(arqc[..], hex("684A609FA0E09F7B"));
+
+        assert!(verify_ac(Algorithm::Tdes, &sk, &data, &arqc));
+        assert!(!verify_ac(Algorithm::Tdes, &sk, &data[1..], &arqc));
+        assert!(!verify_ac(Algorithm::Tdes, &sk, &data, &arqc[..7]));
+
+        assert_eq!(
+            arpc_method1(Algorithm::Tdes, &sk, &arqc, &[0x30, 0x30])[..],
+            hex("8EF9B0E0408AACCB")
+        );
+        assert_eq!(
+            arpc_method2(Algorithm::Tdes, &sk, &arqc, &CSU, &[0xAA, 0xBB])[..],
+            hex("20C3C1EE")
+        );
+    }
+
+    #[test]
+    fn aes_test() {
+        let sk = key("71D6F31A90B363ECC6B4AA2EE21AA365");
+        let data = hex(DATA);
+
+        let arqc = compute_ac(Algorithm::Aes, &sk, &data);
+        assert_eq!(arqc[..], hex("D6D57FBBBA04A904"));
+        assert!(verify_ac(Algorithm::Aes, &sk, &data, &arqc));
+
+        assert_eq!(
+            arpc_method1(Algorithm::Aes, &sk, &arqc, &[0x30, 0x30])[..],
+            hex("355FCE9222F1B22A")
+        );
+        assert_eq!(
+            arpc_method2(Algorithm::Aes, &sk, &arqc, &CSU, &[])[..],
+            hex("A11A0C19")
+        );
+    }
+}
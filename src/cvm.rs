@@ -0,0 +1,596 @@
+//! Cardholder Verification Method List (CVM List, tag `8E`) parsing and evaluation.
+//!
+//! See EMV 4.3 Book 3, section 10.5 and Annex C3.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::bitfield::TerminalCapabilities;
+use crate::tlv::{Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Tag number of CVM List
+pub const CVM_LIST_TAG: usize = 0x8E;
+
+/// Tag number of CVM Results
+pub const CVM_RESULTS_TAG: usize = 0x9F34;
+
+/// Cardholder verification method (bits 6-1 of the first byte of CV Rule)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvmMethod {
+    Fail,
+    PlaintextPinByIcc,
+    EncipheredPinOnline,
+    PlaintextPinByIccAndSignature,
+    EncipheredPinByIcc,
+    EncipheredPinByIccAndSignature,
+    Signature,
+    NoCvmRequired,
+    NotAvailable,
+    /// RFU or payment system/issuer specific method
+    Other(u8),
+}
+
+impl CvmMethod {
+    /// Decodes method from bits 6-1 of CV Rule byte
+    pub fn from_code(code: u8) -> CvmMethod {
+        match code & 0x3F {
+            0x00 => CvmMethod::Fail,
+            0x01 => CvmMethod::PlaintextPinByIcc,
+            0x02 => CvmMethod::EncipheredPinOnline,
+            0x03 => CvmMethod::PlaintextPinByIccAndSignature,
+            0x04 => CvmMethod::EncipheredPinByIcc,
+            0x05 => CvmMethod::EncipheredPinByIccAndSignature,
+            0x1E => CvmMethod::Signature,
+            0x1F => CvmMethod::NoCvmRequired,
+            0x3F => CvmMethod::NotAvailable,
+            x => CvmMethod::Other(x),
+        }
+    }
+
+    /// Returns 6-bit method code
+    pub fn code(&self) -> u8 {
+        match *self {
+            CvmMethod::Fail => 0x00,
+            CvmMethod::PlaintextPinByIcc => 0x01,
+            CvmMethod::EncipheredPinOnline => 0x02,
+            CvmMethod::PlaintextPinByIccAndSignature => 0x03,
+            CvmMethod::EncipheredPinByIcc => 0x04,
+            CvmMethod::EncipheredPinByIccAndSignature => 0x05,
+            CvmMethod::Signature => 0x1E,
+            CvmMethod::NoCvmRequired => 0x1F,
+            CvmMethod::NotAvailable => 0x3F,
+            CvmMethod::Other(x) => x & 0x3F,
+        }
+    }
+
+    /// Returns true if terminal with given capabilities supports the method
+    ///
+    /// `Fail` is always supported, unknown methods are never supported.
+    pub fn is_supported(&self, caps: &TerminalCapabilities) -> bool {
+        match *self {
+            CvmMethod::Fail => true,
+            CvmMethod::PlaintextPinByIcc => caps.plaintext_pin(),
+            CvmMethod::EncipheredPinOnline => caps.enciphered_pin_online(),
+            CvmMethod::PlaintextPinByIccAndSignature => caps.plaintext_pin() && caps.signature(),
+            CvmMethod::EncipheredPinByIcc => caps.enciphered_pin_offline(),
+            CvmMethod::EncipheredPinByIccAndSignature => {
+                caps.enciphered_pin_offline() && caps.signature()
+            }
+            CvmMethod::Signature => caps.signature(),
+            CvmMethod::NoCvmRequired => caps.no_cvm_required(),
+            CvmMethod::NotAvailable | CvmMethod::Other(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for CvmMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CvmMethod::Fail => write!(f, "Fail CVM processing"),
+            CvmMethod::PlaintextPinByIcc => {
+                write!(f, "Plaintext PIN verification performed by ICC")
+            }
+            CvmMethod::EncipheredPinOnline => write!(f, "Enciphered PIN verified online"),
+            CvmMethod::PlaintextPinByIccAndSignature => write!(
+                f,
+                "Plaintext PIN verification performed by ICC and signature (paper)"
+            ),
+            CvmMethod::EncipheredPinByIcc => {
+                write!(f, "Enciphered PIN verification performed by ICC")
+            }
+            CvmMethod::EncipheredPinByIccAndSignature => write!(
+                f,
+                "Enciphered PIN verification performed by ICC and signature (paper)"
+            ),
+            CvmMethod::Signature => write!(f, "Signature (paper)"),
+            CvmMethod::NoCvmRequired => write!(f, "No CVM required"),
+            CvmMethod::NotAvailable => write!(f, "Not available for use"),
+            CvmMethod::Other(x) => write!(f, "Unknown method {:02X}", x),
+        }
+    }
+}
+
+/// Condition under which CV Rule applies (the second byte of CV Rule)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvmCondition {
+    Always,
+    UnattendedCash,
+    NotCashOrCashback,
+    TerminalSupportsCvm,
+    ManualCash,
+    PurchaseWithCashback,
+    UnderX,
+    OverX,
+    UnderY,
+    OverY,
+    /// RFU or payment system specific condition
+    Other(u8),
+}
+
+impl CvmCondition {
+    /// Decodes condition code
+    pub fn from_code(code: u8) -> CvmCondition {
+        match code {
+            0x00 => CvmCondition::Always,
+            0x01 => CvmCondition::UnattendedCash,
+            0x02 => CvmCondition::NotCashOrCashback,
+            0x03 => CvmCondition::TerminalSupportsCvm,
+            0x04 => CvmCondition::ManualCash,
+            0x05 => CvmCondition::PurchaseWithCashback,
+            0x06 => CvmCondition::UnderX,
+            0x07 => CvmCondition::OverX,
+            0x08 => CvmCondition::UnderY,
+            0x09 => CvmCondition::OverY,
+            x => CvmCondition::Other(x),
+        }
+    }
+
+    /// Returns condition code
+    pub fn code(&self) -> u8 {
+        match *self {
+            CvmCondition::Always => 0x00,
+            CvmCondition::UnattendedCash => 0x01,
+            CvmCondition::NotCashOrCashback => 0x02,
+            CvmCondition::TerminalSupportsCvm => 0x03,
+            CvmCondition::ManualCash => 0x04,
+            CvmCondition::PurchaseWithCashback => 0x05,
+            CvmCondition::UnderX => 0x06,
+            CvmCondition::OverX => 0x07,
+            CvmCondition::UnderY => 0x08,
+            CvmCondition::OverY => 0x09,
+            CvmCondition::Other(x) => x,
+        }
+    }
+}
+
+impl fmt::Display for CvmCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CvmCondition::Always => write!(f, "Always"),
+            CvmCondition::UnattendedCash => write!(f, "If unattended cash"),
+            CvmCondition::NotCashOrCashback => write!(
+                f,
+                "If not unattended cash and not manual cash and not purchase with cashback"
+            ),
+            CvmCondition::TerminalSupportsCvm => write!(f, "If terminal supports the CVM"),
+            CvmCondition::ManualCash => write!(f, "If manual cash"),
+            CvmCondition::PurchaseWithCashback => write!(f, "If purchase with cashback"),
+            CvmCondition::UnderX => write!(f, "If under X value"),
+            CvmCondition::OverX => write!(f, "If over X value"),
+            CvmCondition::UnderY => write!(f, "If under Y value"),
+            CvmCondition::OverY => write!(f, "If over Y value"),
+            CvmCondition::Other(x) => write!(f, "Unknown condition {:02X}", x),
+        }
+    }
+}
+
+/// Cardholder Verification Rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CvRule {
+    pub method: CvmMethod,
+    /// Apply succeeding CV Rule if this CVM is unsuccessful
+    pub apply_next_if_unsuccessful: bool,
+    pub condition: CvmCondition,
+}
+
+impl CvRule {
+    /// Decodes 2-byte CV Rule
+    pub fn from_bytes(rule: [u8; 2]) -> CvRule {
+        CvRule {
+            method: CvmMethod::from_code(rule[0]),
+            apply_next_if_unsuccessful: rule[0] & 0x40 != 0,
+            condition: CvmCondition::from_code(rule[1]),
+        }
+    }
+
+    /// Returns CV Rule encoded as 2 bytes
+    pub fn to_bytes(&self) -> [u8; 2] {
+        let mut code = self.method.code();
+        if self.apply_next_if_unsuccessful {
+            code |= 0x40;
+        }
+        [code, self.condition.code()]
+    }
+}
+
+impl fmt::Display for CvRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.method, self.condition)?;
+        if self.apply_next_if_unsuccessful {
+            write!(f, ", apply next rule if unsuccessful")?;
+        }
+        Ok(())
+    }
+}
+
+/// Transaction and terminal data used to evaluate CVM List
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CvmInput {
+    /// Amount, Authorised in the minor units of transaction currency
+    pub amount: u64,
+    /// Transaction Currency Code (`5F2A`)
+    pub transaction_currency: u16,
+    /// Application Currency Code (`9F42`) if provided by card
+    pub application_currency: Option<u16>,
+    /// Terminal Type (`9F35`)
+    pub terminal_type: u8,
+    /// Transaction Type (`9C`)
+    pub transaction_type: u8,
+    /// Terminal Capabilities (`9F33`)
+    pub capabilities: TerminalCapabilities,
+}
+
+impl CvmInput {
+    fn is_unattended(&self) -> bool {
+        matches!(self.terminal_type & 0x0F, 4..=6)
+    }
+
+    fn is_cash(&self) -> bool {
+        self.transaction_type == 0x01
+    }
+
+    fn is_cashback(&self) -> bool {
+        self.transaction_type == 0x09
+    }
+
+    fn in_application_currency(&self) -> bool {
+        self.application_currency == Some(self.transaction_currency)
+    }
+}
+
+/// Result of performed CVM (the third byte of CVM Results)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvmResult {
+    Unknown,
+    Failed,
+    Successful,
+}
+
+/// CVM Results (`9F34`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CvmResults {
+    /// The first byte of performed CV Rule or `3F` if no CVM was performed
+    pub performed: u8,
+    /// Condition code of performed CV Rule
+    pub condition: u8,
+    pub result: CvmResult,
+}
+
+impl CvmResults {
+    /// Returns CVM Results encoded as 3 bytes
+    pub fn to_bytes(&self) -> [u8; 3] {
+        let result = match self.result {
+            CvmResult::Unknown => 0x00,
+            CvmResult::Failed => 0x01,
+            CvmResult::Successful => 0x02,
+        };
+        [self.performed, self.condition, result]
+    }
+
+    /// Returns primitive TLV `9F34`
+    pub fn to_tlv(&self) -> Tlv {
+        Tlv::new(CVM_RESULTS_TAG, Value::Val(self.to_bytes().to_vec()))
+            .expect("CVM Results tag is primitive")
+    }
+
+    fn from_rule(rule: &CvRule, result: CvmResult) -> CvmResults {
+        let bytes = rule.to_bytes();
+        CvmResults {
+            performed: bytes[0],
+            condition: bytes[1],
+            result,
+        }
+    }
+}
+
+/// CVM List
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CvmList {
+    /// Amount X in the minor units of application currency
+    pub amount_x: u32,
+    /// Amount Y in the minor units of application currency
+    pub amount_y: u32,
+    rules: Vec<CvRule>,
+}
+
+impl CvmList {
+    /// Creates CVM List
+    pub fn new(amount_x: u32, amount_y: u32, rules: Vec<CvRule>) -> CvmList {
+        CvmList {
+            amount_x,
+            amount_y,
+            rules,
+        }
+    }
+
+    /// Parses CVM List from value of data object `8E`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::cvm::*;
+    /// let list = CvmList::from_vec(&[
+    ///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x03, 0x1E, 0x03,
+    /// ]).unwrap();
+    /// assert_eq!(list.rules().len(), 2);
+    /// assert_eq!(list.rules()[0].method, CvmMethod::EncipheredPinOnline);
+    /// assert!(list.rules()[0].apply_next_if_unsuccessful);
+    /// assert_eq!(list.rules()[1].condition, CvmCondition::TerminalSupportsCvm);
+    /// ```
+    pub fn from_vec(list: &[u8]) -> Result<CvmList> {
+        if list.len() < 8 || list.len() % 2 != 0 {
+            return Err(TlvError::InvalidValue {
+                tag_number: CVM_LIST_TAG,
+            });
+        }
+
+        let rules = list[8..]
+            .chunks_exact(2)
+            .map(|x| CvRule::from_bytes([x[0], x[1]]))
+            .collect();
+
+        Ok(CvmList {
+            amount_x: u32::from_be_bytes([list[0], list[1], list[2], list[3]]),
+            amount_y: u32::from_be_bytes([list[4], list[5], list[6], list[7]]),
+            rules,
+        })
+    }
+
+    /// Parses CVM List from value of TLV
+    pub fn from_value(value: &Value) -> Result<CvmList> {
+        match *value {
+            Value::Val(ref v) => CvmList::from_vec(v),
+            _ => Err(TlvError::ValExpected {
+                tag_number: CVM_LIST_TAG,
+            }),
+        }
+    }
+
+    /// Parses CVM List from data object `8E`
+    pub fn from_tlv(tlv: &Tlv) -> Result<CvmList> {
+        if tlv.tag() != CVM_LIST_TAG {
+            return Err(TlvError::InvalidTagNumber);
+        }
+
+        CvmList::from_value(tlv.val())
+    }
+
+    /// Returns CV Rules
+    pub fn rules(&self) -> &[CvRule] {
+        &self.rules
+    }
+
+    /// Returns CVM List encoded as array of bytes
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + self.rules.len() * 2);
+        out.extend_from_slice(&self.amount_x.to_be_bytes());
+        out.extend_from_slice(&self.amount_y.to_be_bytes());
+        for rule in &self.rules {
+            out.extend_from_slice(&rule.to_bytes());
+        }
+        out
+    }
+
+    /// Selects CVM to perform and returns resulting CVM Results
+    ///
+    /// Outcome of PIN and signature verification is not known at this point, so
+    /// result of such methods is `CvmResult::Unknown`; "No CVM required" is successful.
+    /// Empty CVM List yields `3F0000` as for card not supporting cardholder
+    /// verification (EMV 4.3 Book 3, section 10.5).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::cvm::*;
+    /// # use tlv_parser::bitfield::TerminalCapabilities;
+    /// let list = CvmList::from_vec(&[
+    ///     0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x03, 0x1E, 0x03,
+    /// ]).unwrap();
+    ///
+    /// let mut input = CvmInput::default();
+    /// input.capabilities = TerminalCapabilities::from([0xE0, 0x20, 0x00]);
+    /// assert_eq!(list.evaluate(&input).to_bytes(), [0x1E, 0x03, 0x00]);
+    ///
+    /// input.capabilities.set_signature(false);
+    /// assert_eq!(list.evaluate(&input).to_bytes(), [0x3F, 0x00, 0x01]);
+    /// ```
+    pub fn evaluate(&self, input: &CvmInput) -> CvmResults {
+        if self.rules.is_empty() {
+            return CvmResults {
+                performed: 0x3F,
+                condition: 0x00,
+                result: CvmResult::Unknown,
+            };
+        }
+
+        let mut failed = None;
+
+        for rule in &self.rules {
+            if !self.is_satisfied(rule, input) {
+                continue;
+            }
+
+            let performed =
+                rule.method != CvmMethod::Fail && rule.method.is_supported(&input.capabilities);
+
+            if performed {
+                let result = match rule.method {
+                    CvmMethod::NoCvmRequired => CvmResult::Successful,
+                    _ => CvmResult::Unknown,
+                };
+                return CvmResults::from_rule(rule, result);
+            }
+
+            failed = Some(rule);
+            if !rule.apply_next_if_unsuccessful {
+                break;
+            }
+        }
+
+        match failed {
+            Some(rule) => CvmResults::from_rule(rule, CvmResult::Failed),
+            None => CvmResults {
+                performed: 0x3F,
+                condition: 0x00,
+                result: CvmResult::Failed,
+            },
+        }
+    }
+
+    fn is_satisfied(&self, rule: &CvRule, input: &CvmInput) -> bool {
+        let amount_x = self.amount_x as u64;
+        let amount_y = self.amount_y as u64;
+
+        match rule.condition {
+            CvmCondition::Always => true,
+            CvmCondition::UnattendedCash => input.is_unattended() && input.is_cash(),
+            CvmCondition::NotCashOrCashback => !input.is_cash() && !input.is_cashback(),
+            CvmCondition::TerminalSupportsCvm => rule.method.is_supported(&input.capabilities),
+            CvmCondition::ManualCash => !input.is_unattended() && input.is_cash(),
+            CvmCondition::PurchaseWithCashback => input.is_cashback(),
+            CvmCondition::UnderX => input.in_application_currency() && input.amount < amount_x,
+            CvmCondition::OverX => input.in_application_currency() && input.amount > amount_x,
+            CvmCondition::UnderY => input.in_application_currency() && input.amount < amount_y,
+            CvmCondition::OverY => input.in_application_currency() && input.amount > amount_y,
+            CvmCondition::Other(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for CvmList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "X={}, Y={}", self.amount_x, self.amount_y)?;
+        for rule in &self.rules {
+            write!(f, "\n{}", rule)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn parse_test() {
+        // CVM List from Amex CDA trace
+        let tlv = Tlv::from_vec(&[
+            0x8E, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x03, 0x1E, 0x03,
+        ])
+        .unwrap();
+        let list = CvmList::from_tlv(&tlv).unwrap();
+
+        assert_eq!(list.amount_x, 0);
+        assert_eq!(list.amount_y, 0);
+        assert_eq!(list.to_vec(), tlv.val().to_vec());
+        assert_eq!(
+            list.to_string(),
+            "X=0, Y=0\n\
+             Enciphered PIN verified online, If terminal supports the CVM, \
+             apply next rule if unsuccessful\n\
+             Signature (paper), If terminal supports the CVM"
+        );
+
+        assert!(CvmList::from_vec(&[0x00; 7]).is_err());
+        assert!(CvmList::from_vec(&[0x00; 9]).is_err());
+    }
+
+    #[test]
+    fn evaluate_amount_test() {
+        let list = CvmList::new(
+            1000,
+            0,
+            vec![
+                CvRule::from_bytes([0x44, 0x07]),
+                CvRule::from_bytes([0x1F, 0x06]),
+                CvRule::from_bytes([0x00, 0x00]),
+            ],
+        );
+
+        let mut input = CvmInput {
+            amount: 500,
+            transaction_currency: 0x0840,
+            application_currency: Some(0x0840),
+            terminal_type: 0x22,
+            transaction_type: 0x00,
+            capabilities: TerminalCapabilities::from([0xE0, 0xB8, 0xC8]),
+        };
+        assert_eq!(list.evaluate(&input).to_bytes(), [0x1F, 0x06, 0x02]);
+
+        input.amount = 1500;
+        assert_eq!(list.evaluate(&input).to_bytes(), [0x44, 0x07, 0x00]);
+
+        // amount conditions are not satisfied in other currency, last rule fails CVM
+        input.transaction_currency = 0x0978;
+        assert_eq!(list.evaluate(&input).to_bytes(), [0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn evaluate_fail_next_test() {
+        let list = CvmList::new(
+            0,
+            0,
+            vec![
+                CvRule::from_bytes([0x41, 0x00]),
+                CvRule::from_bytes([0x02, 0x00]),
+                CvRule::from_bytes([0x1E, 0x00]),
+            ],
+        );
+
+        // offline PIN is not supported, continue to online PIN which fails without next rule
+        let input = CvmInput {
+            capabilities: TerminalCapabilities::from([0xE0, 0x20, 0x00]),
+            ..CvmInput::default()
+        };
+        assert_eq!(list.evaluate(&input).to_bytes(), [0x02, 0x00, 0x01]);
+
+        // cash at unattended terminal
+        let list = CvmList::new(0, 0, vec![CvRule::from_bytes([0x02, 0x01])]);
+        let input = CvmInput {
+            terminal_type: 0x14,
+            transaction_type: 0x01,
+            capabilities: TerminalCapabilities::from([0x60, 0x40, 0x00]),
+            ..CvmInput::default()
+        };
+        assert_eq!(list.evaluate(&input).to_bytes(), [0x02, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn evaluate_empty_test() {
+        let list = CvmList::from_vec(&[0x00; 8]).unwrap();
+        assert_eq!(
+            list.evaluate(&CvmInput::default()).to_bytes(),
+            [0x3F, 0x00, 0x00]
+        );
+
+        // no rule is applicable
+        let list = CvmList::new(0, 0, vec![CvRule::from_bytes([0x1E, 0x03])]);
+        assert_eq!(
+            list.evaluate(&CvmInput::default()).to_bytes(),
+            [0x3F, 0x00, 0x01]
+        );
+    }
+}
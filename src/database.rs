@@ -0,0 +1,331 @@
+//! Flat TLV Database of EMV kernel.
+//!
+//! Kernel keeps data objects keyed by tag together with their source, templates of card
+//! responses are flattened on ingestion. See EMV Contactless Book C-2, section 4.1.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::directory;
+use crate::dol::{Dol, DolSource};
+use crate::tlv::{Tag, Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Templates whose children are stored instead of template itself
+pub const TEMPLATES: [Tag; 5] = [0x70, 0x77, 0x6F, 0xA5, 0xBF0C];
+
+/// Provenance of data object
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    /// Terminal configuration or transaction data
+    Terminal,
+    /// Data object produced by kernel itself
+    Kernel,
+    /// Response of card
+    Card,
+    /// Authorisation response of issuer
+    Issuer,
+}
+
+/// Value of data object and its provenance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub value: Vec<u8>,
+    pub source: Source,
+}
+
+/// TLV Database keyed by tag
+///
+/// Card may provide each data object only once. If update rule is set for the tag,
+/// only listed sources may write it; otherwise any source may.
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::database::*;
+/// # use tlv_parser::tlv::Tlv;
+/// let mut db = TlvDatabase::new();
+/// db.set_rule(0x9F02, &[Source::Terminal]);
+///
+/// let record = Tlv::from_vec(&[0x70, 0x06, 0x5F, 0x34, 0x01, 0x00, 0x82, 0x00]).unwrap();
+/// db.ingest(&record, Source::Card).unwrap();
+///
+/// assert_eq!(db.get(0x5F34), Some(&[0x00][..]));
+/// assert_eq!(db.source(0x5F34), Some(Source::Card));
+/// assert!(db.get(0x70).is_none());
+///
+/// // card can't provide the same data object twice
+/// assert!(db.ingest(&record, Source::Card).is_err());
+/// // and can't write terminal data
+/// assert!(db.insert(0x9F02, vec![0; 6], Source::Card).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlvDatabase {
+    entries: BTreeMap<Tag, Entry>,
+    rules: BTreeMap<Tag, Vec<Source>>,
+}
+
+impl TlvDatabase {
+    /// Returns empty database without update rules
+    pub fn new() -> TlvDatabase {
+        TlvDatabase::default()
+    }
+
+    /// Restricts sources allowed to write given tag
+    pub fn set_rule(&mut self, tag: Tag, sources: &[Source]) {
+        self.rules.insert(tag, sources.to_vec());
+    }
+
+    /// Returns true if source may write the tag now
+    pub fn is_update_allowed(&self, tag: Tag, source: Source) -> bool {
+        self.check(tag, source).is_ok()
+    }
+
+    /// Stores value of data object
+    pub fn insert(&mut self, tag: Tag, value: Vec<u8>, source: Source) -> Result<()> {
+        self.check(tag, source)?;
+        self.entries.insert(tag, Entry { value, source });
+        Ok(())
+    }
+
+    /// Stores data object, children of templates `70`, `77`, `6F`, `A5` and `BF0C`
+    /// are stored recursively
+    ///
+    /// Directory Entries `61` may repeat, so they are skipped and left to
+    /// [`crate::directory`]. Nothing is stored if any of data objects is rejected
+    /// or malformed.
+    pub fn ingest(&mut self, tlv: &Tlv, source: Source) -> Result<()> {
+        let mut objects = Vec::new();
+        flatten(tlv, &mut objects)?;
+
+        let mut seen = BTreeSet::new();
+        for &(tag, _) in &objects {
+            self.check(tag, source)?;
+            if !seen.insert(tag) && source == Source::Card {
+                return Err(TlvError::DuplicateTag { tag_number: tag });
+            }
+        }
+
+        for (tag, value) in objects {
+            self.entries.insert(tag, Entry { value, source });
+        }
+        Ok(())
+    }
+
+    /// Removes data object
+    pub fn remove(&mut self, tag: Tag) -> Option<Entry> {
+        self.entries.remove(&tag)
+    }
+
+    /// Returns value of data object
+    pub fn get(&self, tag: Tag) -> Option<&[u8]> {
+        self.entries.get(&tag).map(|x| &x.value[..])
+    }
+
+    /// Returns value of data object and its provenance
+    pub fn entry(&self, tag: Tag) -> Option<&Entry> {
+        self.entries.get(&tag)
+    }
+
+    /// Returns provenance of data object
+    pub fn source(&self, tag: Tag) -> Option<Source> {
+        self.entries.get(&tag).map(|x| x.source)
+    }
+
+    /// Returns data object as TLV (constructed data objects are parsed)
+    pub fn find(&self, tag: Tag) -> Option<Tlv> {
+        let value = self.get(tag)?;
+        Tlv::new(tag, Value::Val(value.to_vec())).ok()
+    }
+
+    /// Builds DOL related data from stored data objects
+    pub fn build_dol(&self, dol: &Dol) -> Vec<u8> {
+        dol.build(self)
+    }
+
+    /// Returns iterator over stored data objects in ascending order of tags
+    pub fn iter(&self) -> impl Iterator<Item = (Tag, &Entry)> {
+        self.entries.iter().map(|(&tag, entry)| (tag, entry))
+    }
+
+    /// Returns number of stored data objects
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if database is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn check(&self, tag: Tag, source: Source) -> Result<()> {
+        if let Some(sources) = self.rules.get(&tag) {
+            if !sources.contains(&source) {
+                return Err(TlvError::UpdateNotAllowed { tag_number: tag });
+            }
+        }
+
+        match self.entries.get(&tag) {
+            Some(entry) if entry.source == Source::Card && source == Source::Card => {
+                Err(TlvError::DuplicateTag { tag_number: tag })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl DolSource for TlvDatabase {
+    fn dol_value(&self, tag: Tag) -> Option<&[u8]> {
+        self.get(tag)
+    }
+}
+
+fn flatten(tlv: &Tlv, out: &mut Vec<(Tag, Vec<u8>)>) -> Result<()> {
+    match *tlv.val() {
+        Value::TlvList(ref list) if TEMPLATES.contains(&tlv.tag()) => {
+            return list.iter().try_for_each(|x| flatten(x, out));
+        }
+        Value::TlvList(_) if tlv.tag() == directory::ENTRY_TAG => (),
+        Value::Invalid { ref error, .. } => return Err(error.clone()),
+        ref val => out.push((tlv.tag(), val.to_vec())),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tlv::ParseOptions;
+    use alloc::vec;
+
+    #[test]
+    fn ingest_test() {
+        // FCI of ADF
+        let fci = Tlv::from_vec(&[
+            0x6F, 0x1C, 0x84, 0x07, 0xA0, 0x00, 0x00, 0x00, 0x04, 0x10, 0x10, 0xA5, 0x11, 0x50,
+            0x04, 0x54, 0x45, 0x53, 0x54, 0xBF, 0x0C, 0x05, 0x9F, 0x4D, 0x02, 0x0B, 0x0A, 0x87,
+            0x01, 0x01,
+        ])
+        .unwrap();
+
+        let mut db = TlvDatabase::new();
+        db.ingest(&fci, Source::Card).unwrap();
+
+        assert_eq!(db.len(), 4);
+        assert_eq!(db.get(0x50), Some(&b"TEST"[..]));
+        assert_eq!(db.get(0x9F4D), Some(&[0x0B, 0x0A][..]));
+        assert_eq!(db.get(0x87), Some(&[0x01][..]));
+        assert!(db.get(0xA5).is_none());
+
+        // duplicate inside single response is rejected atomically
+        let record = Tlv::new(
+            0x70,
+            Value::TlvList(vec![
+                Tlv::new(0x5A, Value::Val(vec![0x47, 0x61])).unwrap(),
+                Tlv::new(0x5F34, Value::Val(vec![0x00])).unwrap(),
+                Tlv::new(0x5F34, Value::Val(vec![0x01])).unwrap(),
+            ]),
+        )
+        .unwrap();
+        assert!(matches!(
+            db.ingest(&record, Source::Card),
+            Err(TlvError::DuplicateTag { tag_number: 0x5F34 })
+        ));
+        assert!(db.get(0x5A).is_none());
+    }
+
+    #[test]
+    fn rules_test() {
+        let mut db = TlvDatabase::new();
+        db.set_rule(0x9F37, &[Source::Terminal, Source::Kernel]);
+
+        db.insert(0x9F37, vec![1, 2, 3, 4], Source::Terminal)
+            .unwrap();
+        db.insert(0x9F37, vec![5, 6, 7, 8], Source::Kernel).unwrap();
+        assert_eq!(db.entry(0x9F37).unwrap().source, Source::Kernel);
+
+        assert!(!db.is_update_allowed(0x9F37, Source::Card));
+        assert!(matches!(
+            db.insert(0x9F37, vec![0; 4], Source::Card),
+            Err(TlvError::UpdateNotAllowed { tag_number: 0x9F37 })
+        ));
+
+        // terminal data without rule may be updated, card data may not
+        db.insert(0x9A, vec![0x24, 0x10, 0x19], Source::Terminal)
+            .unwrap();
+        db.insert(0x9A, vec![0x24, 0x10, 0x20], Source::Terminal)
+            .unwrap();
+        db.insert(0x5A, vec![0x47, 0x61], Source::Card).unwrap();
+        assert!(!db.is_update_allowed(0x5A, Source::Card));
+        assert!(db.is_update_allowed(0x5A, Source::Issuer));
+
+        assert_eq!(db.remove(0x5A).unwrap().value, [0x47, 0x61]);
+        assert!(db.is_update_allowed(0x5A, Source::Card));
+    }
+
+    #[test]
+    fn lookup_test() {
+        let mut db = TlvDatabase::new();
+        db.insert(
+            0x9F02,
+            vec![0x00, 0x00, 0x00, 0x00, 0x10, 0x00],
+            Source::Terminal,
+        )
+        .unwrap();
+        db.insert(0x9F37, vec![0xDE, 0xAD, 0xBE, 0xEF], Source::Terminal)
+            .unwrap();
+
+        let fci = Tlv::from_vec(&[0xBF, 0x0C, 0x05, 0x73, 0x03, 0x4F, 0x01, 0xA0]).unwrap();
+        db.ingest(
+            &Tlv::new(0xA5, Value::TlvList(vec![fci])).unwrap(),
+            Source::Card,
+        )
+        .unwrap();
+
+        let entry = db.find(0x73).unwrap();
+        assert_eq!(entry.find_val("73 / 4F"), Some(&Value::Val(vec![0xA0])));
+        assert!(db.find(0xBF0C).is_none());
+
+        let dol = Dol::from_vec(&[0x9F, 0x37, 0x04, 0x9F, 0x02, 0x04, 0x9F, 0x1A, 0x02]).unwrap();
+        assert_eq!(
+            db.build_dol(&dol),
+            [0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00]
+        );
+
+        assert_eq!(
+            db.iter().map(|(tag, _)| tag).collect::<Vec<_>>(),
+            [0x73, 0x9F02, 0x9F37]
+        );
+    }
+
+    #[test]
+    fn ppse_test() {
+        // PPSE FCI with two Directory Entries
+        let fci = Tlv::from_vec(&[
+            0x6F, 0x1E, 0x84, 0x0E, 0x32, 0x50, 0x41, 0x59, 0x2E, 0x53, 0x59, 0x53, 0x2E, 0x44,
+            0x44, 0x46, 0x30, 0x31, 0xA5, 0x0C, 0xBF, 0x0C, 0x09, 0x61, 0x03, 0x87, 0x01, 0x01,
+            0x61, 0x02, 0x87, 0x00,
+        ])
+        .unwrap();
+
+        let mut db = TlvDatabase::new();
+        db.ingest(&fci, Source::Card).unwrap();
+        assert_eq!(db.get(0x84), Some(&b"2PAY.SYS.DDF01"[..]));
+        assert!(db.get(0x61).is_none());
+    }
+
+    #[test]
+    fn invalid_test() {
+        let input = [0x70, 0x07, 0x5F, 0x34, 0x01, 0x00, 0x5A, 0x05, 0x47];
+        let (record, _) = Tlv::from_vec_lenient(&input, &ParseOptions::new()).unwrap();
+
+        let mut db = TlvDatabase::new();
+        assert!(matches!(
+            db.ingest(&record, Source::Card)
+                .map_err(|x| x.innermost().clone()),
+            Err(TlvError::TooShortBody { .. })
+        ));
+        assert!(db.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+//! Structural comparison of TLV trees.
+//!
+//! Children are matched by tag and occurrence index of the tag within parent,
+//! so reordering of different tags is not reported.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::tlv::{Tag, Tlv, Value};
+
+/// Path element: tag and occurrence index of the tag within parent
+pub type PathElement = (Tag, usize);
+
+/// Difference between two TLV trees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// Data object exists only in new tree
+    Added { path: Vec<PathElement>, tlv: Tlv },
+    /// Data object exists only in old tree
+    Removed { path: Vec<PathElement>, tlv: Tlv },
+    /// Value of primitive data object differs
+    Modified {
+        path: Vec<PathElement>,
+        old: Vec<u8>,
+        new: Vec<u8>,
+    },
+}
+
+impl Change {
+    /// Returns path to changed data object
+    pub fn path(&self) -> &[PathElement] {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } => path,
+            Change::Modified { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    /// Renders change in unified-diff-like form: removed values are prefixed with `-`,
+    /// added values with `+`; occurrence index is shown for repeated tags only
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path = DisplayPath(self.path());
+
+        match self {
+            Change::Added { tlv, .. } => write!(f, "+ {}: {}", path, Hex(&tlv.val().to_vec())),
+            Change::Removed { tlv, .. } => write!(f, "- {}: {}", path, Hex(&tlv.val().to_vec())),
+            Change::Modified { old, new, .. } => {
+                write!(f, "- {}: {}\n+ {}: {}", path, Hex(old), path, Hex(new))
+            }
+        }
+    }
+}
+
+/// Compares two TLV trees
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::Tlv;
+/// # use tlv_parser::diff::diff;
+/// let old = Tlv::from_vec(&[0x70, 0x06, 0x5F, 0x34, 0x01, 0x00, 0x82, 0x00]).unwrap();
+/// let new = Tlv::from_vec(&[0x70, 0x07, 0x5F, 0x34, 0x01, 0x01, 0x9F, 0x36, 0x00]).unwrap();
+///
+/// let text: Vec<_> = diff(&old, &new).iter().map(|x| x.to_string()).collect();
+/// assert_eq!(text, ["- 70 / 5F34: 00\n+ 70 / 5F34: 01", "- 70 / 82: ", "+ 70 / 9F36: "]);
+/// ```
+pub fn diff(old: &Tlv, new: &Tlv) -> Vec<Change> {
+    diff_list(core::slice::from_ref(old), core::slice::from_ref(new))
+}
+
+/// Compares two sequences of TLV data objects
+pub fn diff_list(old: &[Tlv], new: &[Tlv]) -> Vec<Change> {
+    let mut out = Vec::new();
+    diff_children(old, new, &mut Vec::new(), &mut out);
+    out
+}
+
+fn diff_children(old: &[Tlv], new: &[Tlv], path: &mut Vec<PathElement>, out: &mut Vec<Change>) {
+    for (i, o) in old.iter().enumerate() {
+        let idx = occurrence(old, i);
+        path.push((o.tag(), idx));
+
+        match nth(new, o.tag(), idx) {
+            Some(n) => diff_node(o, n, path, out),
+            None => out.push(Change::Removed {
+                path: path.clone(),
+                tlv: o.clone(),
+            }),
+        }
+
+        path.pop();
+    }
+
+    for (i, n) in new.iter().enumerate() {
+        let idx = occurrence(new, i);
+        if nth(old, n.tag(), idx).is_none() {
+            path.push((n.tag(), idx));
+            out.push(Change::Added {
+                path: path.clone(),
+                tlv: n.clone(),
+            });
+            path.pop();
+        }
+    }
+}
+
+fn diff_node(old: &Tlv, new: &Tlv, path: &mut Vec<PathElement>, out: &mut Vec<Change>) {
+    match (old.val(), new.val()) {
+        (Value::TlvList(o), Value::TlvList(n)) => diff_children(o, n, path, out),
+        _ => {
+            let (o, n) = (old.val().to_vec(), new.val().to_vec());
+            if o != n {
+                out.push(Change::Modified {
+                    path: path.clone(),
+                    old: o,
+                    new: n,
+                });
+            }
+        }
+    }
+}
+
+/// Returns occurrence index of `list[i]` among data objects with the same tag
+fn occurrence(list: &[Tlv], i: usize) -> usize {
+    list[..i]
+        .iter()
+        .filter(|x| x.tag() == list[i].tag())
+        .count()
+}
+
+fn nth(list: &[Tlv], tag: Tag, idx: usize) -> Option<&Tlv> {
+    list.iter().filter(|x| x.tag() == tag).nth(idx)
+}
+
+struct DisplayPath<'a>(&'a [PathElement]);
+
+impl fmt::Display for DisplayPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &(tag, idx)) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, " / ")?;
+            }
+            write!(f, "{:02X}", tag)?;
+            if idx != 0 {
+                write!(f, "[{}]", idx)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|x| write!(f, "{:02X}", x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn diff_test() {
+        let old = Tlv::from_vec(&[
+            0x6F, 0x10, 0x84, 0x02, 0x32, 0x50, 0xA5, 0x0A, 0x87, 0x01, 0x01, 0x87, 0x01, 0x02,
+            0x9F, 0x38, 0x01, 0x00,
+        ])
+        .unwrap();
+        let new = Tlv::from_vec(&[
+            0x6F, 0x11, 0xA5, 0x09, 0x87, 0x01, 0x01, 0x87, 0x01, 0x03, 0x87, 0x01, 0x04, 0x84,
+            0x02, 0x32, 0x50, 0x50, 0x00,
+        ])
+        .unwrap();
+
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes,
+            [
+                Change::Modified {
+                    path: vec![(0x6F, 0), (0xA5, 0), (0x87, 1)],
+                    old: vec![0x02],
+                    new: vec![0x03],
+                },
+                Change::Removed {
+                    path: vec![(0x6F, 0), (0xA5, 0), (0x9F38, 0)],
+                    tlv: Tlv::from_vec(&[0x9F, 0x38, 0x01, 0x00]).unwrap(),
+                },
+                Change::Added {
+                    path: vec![(0x6F, 0), (0xA5, 0), (0x87, 2)],
+                    tlv: Tlv::from_vec(&[0x87, 0x01, 0x04]).unwrap(),
+                },
+                Change::Added {
+                    path: vec![(0x6F, 0), (0x50, 0)],
+                    tlv: Tlv::from_vec(&[0x50, 0x00]).unwrap(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            changes[0].to_string(),
+            "- 6F / A5 / 87[1]: 02\n+ 6F / A5 / 87[1]: 03"
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn root_test() {
+        let old = Tlv::from_vec(&[0x70, 0x03, 0x82, 0x01, 0x00]).unwrap();
+        let new = Tlv::from_vec(&[0x77, 0x03, 0x82, 0x01, 0x00]).unwrap();
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].to_string(), "- 70: 820100");
+        assert_eq!(changes[1].to_string(), "+ 77: 820100");
+    }
+}
@@ -0,0 +1,254 @@
+//! PSE/PPSE directory entries and candidate list construction.
+//!
+//! Directory entries (template `61`) are returned in FCI Issuer Discretionary Data (`BF0C`)
+//! of SELECT PPSE response or in records (`70`) of PSE directory.
+//! See EMV 4.3 Book 1, section 12.3 and EMV Contactless Book B, section 3.3.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::tlv::{Tag, Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Tag number of Application Template (directory entry)
+pub const ENTRY_TAG: Tag = 0x61;
+
+/// Tag number of ADF Name
+pub const AID_TAG: Tag = 0x4F;
+
+/// Tag number of Application Label
+pub const LABEL_TAG: Tag = 0x50;
+
+/// Tag number of Application Priority Indicator
+pub const PRIORITY_TAG: Tag = 0x87;
+
+/// Tag number of Kernel Identifier
+pub const KERNEL_ID_TAG: Tag = 0x9F2A;
+
+/// Tag number of Extended Selection
+pub const EXTENDED_SELECTION_TAG: Tag = 0x9F29;
+
+/// Entry of PSE/PPSE directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryEntry {
+    pub aid: Vec<u8>,
+    pub label: Option<String>,
+    pub priority: Option<u8>,
+    pub kernel_id: Option<Vec<u8>>,
+    pub extended_selection: Option<Vec<u8>>,
+}
+
+impl DirectoryEntry {
+    /// Parses directory entry (template `61`)
+    pub fn from_tlv(tlv: &Tlv) -> Result<DirectoryEntry> {
+        let list = match (tlv.tag(), tlv.val()) {
+            (ENTRY_TAG, Value::TlvList(list)) => list,
+            _ => return Err(TlvError::InvalidTagNumber),
+        };
+
+        let find = |tag| match list.iter().find(|x| x.tag() == tag).map(|x| x.val()) {
+            Some(Value::Val(v)) => Some(v.clone()),
+            _ => None,
+        };
+
+        let aid = find(AID_TAG).ok_or(TlvError::InvalidValue {
+            tag_number: ENTRY_TAG,
+        })?;
+
+        let priority = match find(PRIORITY_TAG) {
+            Some(v) if v.len() == 1 => Some(v[0]),
+            Some(_) => {
+                return Err(TlvError::InvalidValue {
+                    tag_number: PRIORITY_TAG,
+                })
+            }
+            None => None,
+        };
+
+        Ok(DirectoryEntry {
+            aid,
+            label: find(LABEL_TAG).map(|v| String::from_utf8_lossy(&v).into_owned()),
+            priority,
+            kernel_id: find(KERNEL_ID_TAG),
+            extended_selection: find(EXTENDED_SELECTION_TAG),
+        })
+    }
+
+    /// Returns priority (1 is the highest) or `None` if entry has no priority
+    pub fn priority_order(&self) -> Option<u8> {
+        self.priority.map(|x| x & 0x0F).filter(|&x| x != 0)
+    }
+
+    /// Returns true if cardholder confirmation is required to select application
+    pub fn confirmation_required(&self) -> bool {
+        self.priority.is_some_and(|x| x & 0x80 != 0)
+    }
+}
+
+/// Extracts directory entries from SELECT PPSE response (`6F`) or PSE record (`70`)
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::Tlv;
+/// # use tlv_parser::directory::directory_entries;
+/// let fci = Tlv::from_vec(&[
+///     0x6F, 0x15, 0x84, 0x02, 0x32, 0x50, 0xA5, 0x0F, 0xBF, 0x0C, 0x0C, 0x61, 0x0A, 0x4F,
+///     0x05, 0xA0, 0x00, 0x00, 0x00, 0x03, 0x87, 0x01, 0x01,
+/// ]).unwrap();
+///
+/// let entries = directory_entries(&fci).unwrap();
+/// assert_eq!(entries[0].aid, [0xA0, 0x00, 0x00, 0x00, 0x03]);
+/// assert_eq!(entries[0].priority_order(), Some(1));
+/// ```
+pub fn directory_entries(tlv: &Tlv) -> Result<Vec<DirectoryEntry>> {
+    let list = match tlv.tag() {
+        0x6F => tlv.find_val("6F / A5 / BF0C"),
+        0x70 => Some(tlv.val()),
+        _ => return Err(TlvError::InvalidTagNumber),
+    };
+
+    match list {
+        Some(Value::TlvList(list)) => list
+            .iter()
+            .filter(|x| x.tag() == ENTRY_TAG)
+            .map(DirectoryEntry::from_tlv)
+            .collect(),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// AID supported by terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupportedAid {
+    pub aid: Vec<u8>,
+    /// Application Selection Indicator: card AID may be longer than terminal AID
+    pub partial_match: bool,
+}
+
+impl SupportedAid {
+    /// Returns true if AID of card matches the configuration
+    pub fn matches(&self, aid: &[u8]) -> bool {
+        if self.partial_match {
+            aid.starts_with(&self.aid)
+        } else {
+            aid == &self.aid[..]
+        }
+    }
+}
+
+/// Application mutually supported by card and terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub entry: DirectoryEntry,
+    /// Terminal configuration matched by entry
+    pub supported: SupportedAid,
+}
+
+/// Builds candidate list ordered by Application Priority Indicator
+///
+/// Each entry is matched against supported AIDs in order; entries without priority
+/// follow prioritized ones, entries with equal priority keep directory order.
+pub fn candidate_list(entries: &[DirectoryEntry], supported: &[SupportedAid]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = entries
+        .iter()
+        .filter_map(|entry| {
+            supported
+                .iter()
+                .find(|x| x.matches(&entry.aid))
+                .map(|x| Candidate {
+                    entry: entry.clone(),
+                    supported: x.clone(),
+                })
+        })
+        .collect();
+
+    candidates.sort_by_key(|x| x.entry.priority_order().unwrap_or(u8::MAX));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn entry(aid: &[u8], priority: Option<u8>) -> DirectoryEntry {
+        DirectoryEntry {
+            aid: aid.to_vec(),
+            label: None,
+            priority,
+            kernel_id: None,
+            extended_selection: None,
+        }
+    }
+
+    #[test]
+    fn ppse_test() {
+        // SELECT PPSE response from Amex CDA trace
+        let fci = Tlv::from_vec(&[
+            0x6F, 0x3B, 0x84, 0x0E, 0x32, 0x50, 0x41, 0x59, 0x2E, 0x53, 0x59, 0x53, 0x2E, 0x44,
+            0x44, 0x46, 0x30, 0x31, 0xA5, 0x29, 0xBF, 0x0C, 0x26, 0x61, 0x24, 0x4F, 0x08, 0xA0,
+            0x00, 0x00, 0x00, 0x25, 0x01, 0x04, 0x03, 0x50, 0x10, 0x41, 0x6D, 0x65, 0x72, 0x69,
+            0x63, 0x61, 0x6E, 0x20, 0x45, 0x78, 0x70, 0x72, 0x65, 0x73, 0x73, 0x87, 0x01, 0x01,
+            0x9F, 0x28, 0x02, 0x40, 0x04,
+        ])
+        .unwrap();
+
+        let entries = directory_entries(&fci).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].aid,
+            [0xA0, 0x00, 0x00, 0x00, 0x25, 0x01, 0x04, 0x03]
+        );
+        assert_eq!(entries[0].label.as_deref(), Some("American Express"));
+        assert_eq!(entries[0].priority_order(), Some(1));
+        assert!(!entries[0].confirmation_required());
+        assert!(entries[0].kernel_id.is_none());
+
+        // entry without AID
+        let fci = Tlv::from_vec(&[0x70, 0x05, 0x61, 0x03, 0x87, 0x01, 0x01]).unwrap();
+        assert!(directory_entries(&fci).is_err());
+
+        let fci = Tlv::from_vec(&[0x6F, 0x04, 0x84, 0x02, 0x32, 0x50]).unwrap();
+        assert!(directory_entries(&fci).unwrap().is_empty());
+    }
+
+    #[test]
+    fn candidate_list_test() {
+        let entries = [
+            entry(&[0xA0, 0x00, 0x00, 0x00, 0x04, 0x10, 0x10], None),
+            entry(&[0xA0, 0x00, 0x00, 0x00, 0x03, 0x10, 0x10], Some(0x82)),
+            entry(&[0xA0, 0x00, 0x00, 0x00, 0x03, 0x20, 0x10], Some(0x01)),
+            entry(&[0xA0, 0x00, 0x00, 0x00, 0x25, 0x01], Some(0x00)),
+            entry(&[0xA0, 0x00, 0x00, 0x00, 0x04, 0x30, 0x60], Some(0x01)),
+        ];
+
+        let supported = [
+            SupportedAid {
+                aid: vec![0xA0, 0x00, 0x00, 0x00, 0x03],
+                partial_match: true,
+            },
+            SupportedAid {
+                aid: vec![0xA0, 0x00, 0x00, 0x00, 0x04, 0x10, 0x10],
+                partial_match: false,
+            },
+            SupportedAid {
+                aid: vec![0xA0, 0x00, 0x00, 0x00, 0x25],
+                partial_match: false,
+            },
+        ];
+
+        let candidates = candidate_list(&entries, &supported);
+        let aids: Vec<_> = candidates.iter().map(|x| &x.entry.aid[..]).collect();
+        assert_eq!(
+            aids,
+            [
+                &[0xA0, 0x00, 0x00, 0x00, 0x03, 0x20, 0x10][..],
+                &[0xA0, 0x00, 0x00, 0x00, 0x03, 0x10, 0x10],
+                &[0xA0, 0x00, 0x00, 0x00, 0x04, 0x10, 0x10],
+            ]
+        );
+        assert!(candidates[1].entry.confirmation_required());
+        assert_eq!(candidates[2].supported, supported[1]);
+    }
+}
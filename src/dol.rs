@@ -0,0 +1,363 @@
+//! Data Object List (DOL) parsing and building.
+//!
+//! DOLs (e.g. PDOL `9F38`, CDOL1 `8C`, CDOL2 `8D`, DDOL `9F49`) are lists of tag and
+//! length pairs which define the layout of data that terminal sends to the card.
+//! See EMV 4.3 Book 3, section 5.4 (Rules for Using a Data Object List).
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use crate::tlv::{Tag, Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Format of data element, which defines padding and truncation rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Numeric (`n`): right justified, padded with leading zeroes
+    Numeric,
+    /// Compressed numeric (`cn`): left justified, padded with trailing `FF`
+    CompressedNumeric,
+    /// Any other format (`a`, `an`, `ans`, `b`): padded with trailing zeroes
+    Other,
+}
+
+impl Format {
+    /// Returns format of EMV data element by its tag
+    ///
+    /// Unknown tags are treated as `Format::Other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::dol::Format;
+    /// assert_eq!(Format::of(0x9F02), Format::Numeric);
+    /// assert_eq!(Format::of(0x5A), Format::CompressedNumeric);
+    /// assert_eq!(Format::of(0x9F37), Format::Other);
+    /// ```
+    pub fn of(tag: Tag) -> Format {
+        match tag {
+            0x5F24 | 0x5F25 | 0x5F28 | 0x5F2A | 0x5F34 | 0x5F36 | 0x5F57 | 0x9A | 0x9C | 0x9F01
+            | 0x9F02 | 0x9F03 | 0x9F11 | 0x9F15 | 0x9F1A | 0x9F21 | 0x9F35 | 0x9F39 | 0x9F3C
+            | 0x9F3D | 0x9F41 | 0x9F42 | 0x9F43 | 0x9F44 => Format::Numeric,
+            0x5A | 0x9F20 => Format::CompressedNumeric,
+            _ => Format::Other,
+        }
+    }
+}
+
+/// Source of data elements used for DOL-related data building
+pub trait DolSource {
+    /// Returns value of primitive data object with given tag
+    fn dol_value(&self, tag: Tag) -> Option<&[u8]>;
+}
+
+impl DolSource for Tlv {
+    fn dol_value(&self, tag: Tag) -> Option<&[u8]> {
+        match *self.val() {
+            Value::Val(ref v) if self.tag() == tag => Some(v),
+            Value::Nothing if self.tag() == tag => Some(&[]),
+            Value::TlvList(ref list) => list.dol_value(tag),
+            _ => None,
+        }
+    }
+}
+
+impl DolSource for [Tlv] {
+    fn dol_value(&self, tag: Tag) -> Option<&[u8]> {
+        self.iter().find_map(|tlv| tlv.dol_value(tag))
+    }
+}
+
+impl DolSource for BTreeMap<Tag, Vec<u8>> {
+    fn dol_value(&self, tag: Tag) -> Option<&[u8]> {
+        self.get(&tag).map(|v| v.as_slice())
+    }
+}
+
+/// Single entry of DOL: tag of data object and its expected length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DolEntry {
+    pub tag: Tag,
+    pub len: usize,
+}
+
+/// Data Object List
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dol {
+    entries: Vec<DolEntry>,
+}
+
+impl Dol {
+    /// Creates DOL from list of entries
+    pub fn new(entries: Vec<DolEntry>) -> Dol {
+        Dol { entries }
+    }
+
+    /// Parses DOL from value of DOL data object (e.g. value of `8C`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::dol::*;
+    /// let dol = Dol::from_vec(&[0x9F, 0x02, 0x06, 0x95, 0x05]).unwrap();
+    /// assert_eq!(dol.entries(), &[
+    ///     DolEntry { tag: 0x9F02, len: 6 },
+    ///     DolEntry { tag: 0x95, len: 5 },
+    /// ]);
+    /// assert_eq!(dol.data_len(), 11);
+    /// ```
+    pub fn from_vec(dol: &[u8]) -> Result<Dol> {
+        let mut iter = dol.iter();
+        let mut entries = Vec::new();
+
+        while iter.len() != 0 {
+            let tag = Tlv::read_tag(&mut iter)?;
+            let len = *iter.next().ok_or(TlvError::TruncatedTlv)? as usize;
+            entries.push(DolEntry { tag, len });
+        }
+
+        Ok(Dol { entries })
+    }
+
+    /// Parses DOL from primitive TLV (e.g. `9F38` or `8C`)
+    pub fn from_tlv(tlv: &Tlv) -> Result<Dol> {
+        match *tlv.val() {
+            Value::Val(ref v) => Dol::from_vec(v),
+            Value::Nothing => Ok(Dol::default()),
+            Value::TlvList(_) => Err(TlvError::ValExpected {
+                tag_number: tlv.tag(),
+            }),
+            Value::Invalid { ref error, .. } => Err(error.clone()),
+        }
+    }
+
+    /// Returns entries of DOL
+    pub fn entries(&self) -> &[DolEntry] {
+        &self.entries
+    }
+
+    /// Returns length of DOL-related data in bytes
+    pub fn data_len(&self) -> usize {
+        self.entries.iter().map(|e| e.len).sum()
+    }
+
+    /// Returns DOL encoded as array of bytes
+    ///
+    /// Length of each entry is coded in one byte, so lengths above 255 are rejected
+    /// with `TlvError::InvalidLength`.
+    pub fn to_vec(&self) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        for entry in &self.entries {
+            out.extend(
+                (entry.tag as u64)
+                    .to_be_bytes()
+                    .iter()
+                    .skip_while(|&&x| x == 0),
+            );
+            out.push(u8::try_from(entry.len).map_err(|_| TlvError::InvalidLength)?);
+        }
+        Ok(out)
+    }
+
+    /// Builds DOL-related data from data source
+    ///
+    /// Format of each data element is determined by `Format::of`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::dol::*;
+    /// # use tlv_parser::tlv::*;
+    /// let source = Tlv::from_vec(&[0x70, 0x06, 0x9F, 0x02, 0x01, 0x15, 0x8A, 0x00]).unwrap();
+    /// let dol = Dol::from_vec(&[0x9F, 0x02, 0x03, 0x9F, 0x37, 0x02]).unwrap();
+    /// assert_eq!(dol.build(&source), vec![0x00, 0x00, 0x15, 0x00, 0x00]);
+    /// ```
+    pub fn build<S: DolSource + ?Sized>(&self, source: &S) -> Vec<u8> {
+        self.build_with(source, Format::of)
+    }
+
+    /// Builds DOL-related data from data source using custom format lookup
+    ///
+    /// Data objects that are absent in source or have constructed tag are filled with zeroes.
+    pub fn build_with<S, F>(&self, source: &S, format: F) -> Vec<u8>
+    where
+        S: DolSource + ?Sized,
+        F: Fn(Tag) -> Format,
+    {
+        let mut out = Vec::with_capacity(self.data_len());
+
+        for entry in &self.entries {
+            let val = if Tlv::is_primitive_tag(entry.tag) {
+                source.dol_value(entry.tag)
+            } else {
+                None
+            };
+
+            match val {
+                Some(val) => fit(&mut out, val, entry.len, format(entry.tag)),
+                None => out.resize(out.len() + entry.len, 0),
+            }
+        }
+
+        out
+    }
+
+    /// Splits DOL-related data into values of data objects
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::dol::*;
+    /// let dol = Dol::from_vec(&[0x9A, 0x03, 0x9C, 0x01]).unwrap();
+    /// let values = dol.split(&[0x24, 0x10, 0x19, 0x00]).unwrap();
+    /// assert_eq!(values, vec![(0x9A, &[0x24, 0x10, 0x19][..]), (0x9C, &[0x00][..])]);
+    /// ```
+    pub fn split<'a>(&self, data: &'a [u8]) -> Result<Vec<(Tag, &'a [u8])>> {
+        let expected = self.data_len();
+        if data.len() < expected {
+            return Err(TlvError::TooShortBody {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        if data.len() > expected {
+            return Err(TlvError::InvalidLength);
+        }
+
+        let mut idx = 0;
+        let values = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let val = &data[idx..idx + entry.len];
+                idx += entry.len;
+                (entry.tag, val)
+            })
+            .collect();
+
+        Ok(values)
+    }
+}
+
+/// Appends value to output adjusting it to requested length per EMV padding rules
+fn fit(out: &mut Vec<u8>, val: &[u8], len: usize, format: Format) {
+    if val.len() >= len {
+        match format {
+            // leftmost bytes are truncated for numeric format
+            Format::Numeric => out.extend_from_slice(&val[val.len() - len..]),
+            _ => out.extend_from_slice(&val[..len]),
+        }
+        return;
+    }
+
+    let pad = len - val.len();
+    match format {
+        Format::Numeric => {
+            out.resize(out.len() + pad, 0);
+            out.extend_from_slice(val);
+        }
+        Format::CompressedNumeric => {
+            out.extend_from_slice(val);
+            out.resize(out.len() + pad, 0xFF);
+        }
+        Format::Other => {
+            out.extend_from_slice(val);
+            out.resize(out.len() + pad, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    // CDOL1 from Amex CDA trace
+    const CDOL1: [u8; 21] = [
+        0x9F, 0x02, 0x06, 0x9F, 0x03, 0x06, 0x9F, 0x1A, 0x02, 0x95, 0x05, 0x5F, 0x2A, 0x02, 0x9A,
+        0x03, 0x9C, 0x01, 0x9F, 0x37, 0x04,
+    ];
+
+    #[test]
+    fn from_vec_test() {
+        let dol = Dol::from_vec(&CDOL1).unwrap();
+        assert_eq!(dol.entries().len(), 8);
+        assert_eq!(dol.entries()[3], DolEntry { tag: 0x95, len: 5 });
+        assert_eq!(dol.data_len(), 29);
+        assert_eq!(dol.to_vec(), Ok(CDOL1.to_vec()));
+
+        let dol = Dol::new(vec![DolEntry {
+            tag: 0x9F4E,
+            len: 256,
+        }]);
+        assert_eq!(dol.to_vec(), Err(TlvError::InvalidLength));
+
+        // missing length of last entry
+        assert!(Dol::from_vec(&[0x9F, 0x02, 0x06, 0x9F, 0x03]).is_err());
+    }
+
+    #[test]
+    fn build_test() {
+        let mut source = BTreeMap::new();
+        source.insert(0x9F02, vec![0x00, 0x00, 0x00, 0x00, 0x10, 0x00]);
+        source.insert(0x9F1A, vec![0x08, 0x40]);
+        source.insert(0x9F37, vec![0x01, 0x02, 0x03, 0x04, 0x05]);
+
+        let dol = Dol::from_vec(&CDOL1).unwrap();
+        let data = dol.build(&source);
+        assert_eq!(data.len(), dol.data_len());
+        assert_eq!(&data[0..6], [0x00, 0x00, 0x00, 0x00, 0x10, 0x00]);
+        assert_eq!(&data[6..12], [0; 6]);
+        assert_eq!(&data[12..14], [0x08, 0x40]);
+        // terminal type and POS entry mode are numeric
+        let dol = Dol::new(vec![
+            DolEntry {
+                tag: 0x9F35,
+                len: 2,
+            },
+            DolEntry {
+                tag: 0x9F39,
+                len: 2,
+            },
+        ]);
+        source.insert(0x9F35, vec![0x22]);
+        source.insert(0x9F39, vec![0x00, 0x00, 0x07]);
+        assert_eq!(dol.build(&source), [0x00, 0x22, 0x00, 0x07]);
+        // unpredictable number is binary and truncated from the right
+        assert_eq!(&data[25..29], [0x01, 0x02, 0x03, 0x04]);
+    }
+
+    #[test]
+    fn fit_test() {
+        let mut out = Vec::new();
+        fit(&mut out, &[0x12, 0x34], 3, Format::Numeric);
+        fit(&mut out, &[0x12, 0x34], 1, Format::Numeric);
+        fit(&mut out, &[0x12, 0x34], 3, Format::CompressedNumeric);
+        fit(&mut out, &[0x12, 0x34], 3, Format::Other);
+        fit(&mut out, &[0x12, 0x34], 1, Format::Other);
+        assert_eq!(
+            out,
+            vec![0x00, 0x12, 0x34, 0x34, 0x12, 0x34, 0xFF, 0x12, 0x34, 0x00, 0x12]
+        );
+    }
+
+    #[test]
+    fn constructed_tag_test() {
+        let source = Tlv::from_vec(&[0x70, 0x04, 0xA5, 0x02, 0x01, 0x00]).unwrap();
+        let dol = Dol::new(vec![DolEntry { tag: 0xA5, len: 2 }]);
+        assert_eq!(dol.build(&source), vec![0x00, 0x00]);
+    }
+
+    #[test]
+    fn split_test() {
+        let dol = Dol::from_vec(&CDOL1).unwrap();
+        let data: Vec<u8> = (0..29).collect();
+        let values = dol.split(&data).unwrap();
+        assert_eq!(values.len(), 8);
+        assert_eq!(values[7], (0x9F37, &[25, 26, 27, 28][..]));
+
+        assert!(dol.split(&data[1..]).is_err());
+        assert!(dol.split(&[0; 30]).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! GENERATE APPLICATION CRYPTOGRAM response normalization.
+//!
+//! Card returns either format 1 (primitive `80` with CID, ATC, AC and optional IAD
+//! concatenated) or format 2 (constructed `77` template). See EMV 4.3 Book 3, section 6.5.5.4.
+
+use alloc::vec::Vec;
+
+use crate::gpo::{find, FORMAT1_TAG, FORMAT2_TAG};
+use crate::tlv::{Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Tag number of Cryptogram Information Data
+pub const CID_TAG: usize = 0x9F27;
+
+/// Tag number of Application Transaction Counter
+pub const ATC_TAG: usize = 0x9F36;
+
+/// Tag number of Application Cryptogram
+pub const AC_TAG: usize = 0x9F26;
+
+/// Tag number of Issuer Application Data
+pub const IAD_TAG: usize = 0x9F10;
+
+/// Type of Application Cryptogram encoded in bits 8-7 of CID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptogramType {
+    /// Application Authentication Cryptogram (transaction declined)
+    Aac,
+    /// Transaction Certificate (transaction approved)
+    Tc,
+    /// Authorisation Request Cryptogram (online authorisation requested)
+    Arqc,
+    /// Reserved for future use
+    Rfu,
+}
+
+impl CryptogramType {
+    /// Returns cryptogram type encoded in Cryptogram Information Data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::gen_ac::CryptogramType;
+    /// assert_eq!(CryptogramType::from_cid(0x80), CryptogramType::Arqc);
+    /// assert_eq!(CryptogramType::from_cid(0x40), CryptogramType::Tc);
+    /// ```
+    pub fn from_cid(cid: u8) -> CryptogramType {
+        match cid >> 6 {
+            0b00 => CryptogramType::Aac,
+            0b01 => CryptogramType::Tc,
+            0b10 => CryptogramType::Arqc,
+            _ => CryptogramType::Rfu,
+        }
+    }
+}
+
+/// GENERATE AC response in uniform representation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenAcResponse {
+    cid: u8,
+    atc: [u8; 2],
+    ac: [u8; 8],
+    iad: Option<Vec<u8>>,
+    tlvs: Vec<Tlv>,
+}
+
+impl GenAcResponse {
+    /// Parses response data field of GENERATE AC command
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::gen_ac::*;
+    /// let resp = GenAcResponse::from_vec(&[
+    ///     0x80, 0x0B, 0x80, 0x00, 0x2A, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88,
+    /// ]).unwrap();
+    /// assert_eq!(resp.cryptogram_type(), CryptogramType::Arqc);
+    /// assert_eq!(resp.atc(), 0x2A);
+    /// assert_eq!(resp.ac(), [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]);
+    /// assert!(resp.iad().is_none());
+    /// ```
+    pub fn from_vec(data: &[u8]) -> Result<GenAcResponse> {
+        GenAcResponse::from_tlv(&Tlv::from_vec(data)?)
+    }
+
+    /// Normalizes format 1 (`80`) or format 2 (`77`) response
+    pub fn from_tlv(tlv: &Tlv) -> Result<GenAcResponse> {
+        match (tlv.tag(), tlv.val()) {
+            (FORMAT1_TAG, Value::Val(v)) => {
+                if v.len() < 11 {
+                    return Err(TlvError::InvalidValue {
+                        tag_number: FORMAT1_TAG,
+                    });
+                }
+
+                let mut ac = [0; 8];
+                ac.copy_from_slice(&v[3..11]);
+
+                Ok(GenAcResponse {
+                    cid: v[0],
+                    atc: [v[1], v[2]],
+                    ac,
+                    iad: if v.len() > 11 {
+                        Some(v[11..].to_vec())
+                    } else {
+                        None
+                    },
+                    tlvs: Vec::new(),
+                })
+            }
+            (FORMAT2_TAG, Value::TlvList(list)) => {
+                let cid = fixed::<1>(list, CID_TAG)?[0];
+                let atc = fixed(list, ATC_TAG)?;
+                let ac = fixed(list, AC_TAG)?;
+                let iad = find(list, IAD_TAG).map(|val| val.to_vec());
+
+                let tlvs = list
+                    .iter()
+                    .filter(|x| ![CID_TAG, ATC_TAG, AC_TAG, IAD_TAG].contains(&x.tag()))
+                    .cloned()
+                    .collect();
+
+                Ok(GenAcResponse {
+                    cid,
+                    atc,
+                    ac,
+                    iad,
+                    tlvs,
+                })
+            }
+            _ => Err(TlvError::InvalidTagNumber),
+        }
+    }
+
+    /// Returns Cryptogram Information Data
+    pub fn cid(&self) -> u8 {
+        self.cid
+    }
+
+    /// Returns type of cryptogram returned by card
+    pub fn cryptogram_type(&self) -> CryptogramType {
+        CryptogramType::from_cid(self.cid)
+    }
+
+    /// Returns Application Transaction Counter
+    pub fn atc(&self) -> u16 {
+        u16::from_be_bytes(self.atc)
+    }
+
+    /// Returns Application Cryptogram
+    pub fn ac(&self) -> [u8; 8] {
+        self.ac
+    }
+
+    /// Returns Issuer Application Data if present
+    pub fn iad(&self) -> Option<&[u8]> {
+        self.iad.as_deref()
+    }
+
+    /// Returns data objects other than CID, ATC, AC and IAD
+    /// (e.g. Signed Dynamic Application Data `9F4B` for CDA; always empty for format 1)
+    pub fn tlvs(&self) -> &[Tlv] {
+        &self.tlvs
+    }
+}
+
+/// Returns value of mandatory primitive child with fixed length
+fn fixed<const N: usize>(list: &[Tlv], tag: usize) -> Result<[u8; N]> {
+    match find(list, tag) {
+        Some(Value::Val(v)) if v.len() == N => {
+            let mut out = [0; N];
+            out.copy_from_slice(v);
+            Ok(out)
+        }
+        _ => Err(TlvError::InvalidValue { tag_number: tag }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format1_test() {
+        let resp = GenAcResponse::from_vec(&[
+            0x80, 0x12, 0x40, 0x00, 0x01, 0xA1, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0x06,
+            0x01, 0x0A, 0x03, 0xA0, 0x00, 0x00,
+        ])
+        .unwrap();
+
+        assert_eq!(resp.cid(), 0x40);
+        assert_eq!(resp.cryptogram_type(), CryptogramType::Tc);
+        assert_eq!(resp.atc(), 1);
+        assert_eq!(
+            resp.iad(),
+            Some(&[0x06, 0x01, 0x0A, 0x03, 0xA0, 0x00, 0x00][..])
+        );
+        assert!(resp.tlvs().is_empty());
+
+        // AC is truncated
+        assert!(GenAcResponse::from_vec(&[0x80, 0x05, 0x40, 0x00, 0x01, 0xA1, 0xA2]).is_err());
+    }
+
+    #[test]
+    fn format2_test() {
+        let resp = GenAcResponse::from_vec(&[
+            0x77, 0x1A, 0x9F, 0x27, 0x01, 0x00, 0x9F, 0x36, 0x02, 0x01, 0x02, 0x9F, 0x26, 0x08,
+            0xA1, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0x9F, 0x4B, 0x03, 0x6A, 0x05, 0xBC,
+        ])
+        .unwrap();
+
+        assert_eq!(resp.cryptogram_type(), CryptogramType::Aac);
+        assert_eq!(resp.atc(), 0x0102);
+        assert_eq!(resp.ac()[7], 0xA8);
+        assert!(resp.iad().is_none());
+        assert_eq!(resp.tlvs().len(), 1);
+        assert_eq!(resp.tlvs()[0].tag(), 0x9F4B);
+
+        // CID is missing
+        assert!(GenAcResponse::from_vec(&[0x77, 0x05, 0x9F, 0x36, 0x02, 0x01, 0x02]).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! GET PROCESSING OPTIONS response normalization.
+//!
+//! Card returns either format 1 (primitive `80` with AIP and AFL concatenated)
+//! or format 2 (constructed `77` template). See EMV 4.3 Book 3, section 6.5.8.4.
+
+use alloc::vec::Vec;
+
+use crate::afl::{Afl, AFL_TAG};
+use crate::bitfield::Aip;
+use crate::tlv::{Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Tag number of Application Interchange Profile
+pub const AIP_TAG: usize = 0x82;
+
+/// Tag number of Response Message Template Format 1
+pub const FORMAT1_TAG: usize = 0x80;
+
+/// Tag number of Response Message Template Format 2
+pub const FORMAT2_TAG: usize = 0x77;
+
+/// GET PROCESSING OPTIONS response in uniform representation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpoResponse {
+    aip: Aip,
+    afl: Afl,
+    tlvs: Vec<Tlv>,
+}
+
+impl GpoResponse {
+    /// Parses response data field of GET PROCESSING OPTIONS command
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::gpo::*;
+    /// // format 1
+    /// let gpo = GpoResponse::from_vec(&[0x80, 0x06, 0x5C, 0x00, 0x08, 0x01, 0x01, 0x00]).unwrap();
+    /// assert_eq!(gpo.aip().bytes(), [0x5C, 0x00]);
+    /// assert_eq!(gpo.afl().records(), vec![(1, 1)]);
+    ///
+    /// // format 2
+    /// let gpo = GpoResponse::from_vec(&[
+    ///     0x77, 0x0F, 0x82, 0x02, 0x5C, 0x00, 0x94, 0x04, 0x08, 0x01, 0x01, 0x00,
+    ///     0x9F, 0x36, 0x02, 0x00, 0x01,
+    /// ]).unwrap();
+    /// assert_eq!(gpo.aip().bytes(), [0x5C, 0x00]);
+    /// assert_eq!(gpo.tlvs()[0].tag(), 0x9F36);
+    /// ```
+    pub fn from_vec(data: &[u8]) -> Result<GpoResponse> {
+        GpoResponse::from_tlv(&Tlv::from_vec(data)?)
+    }
+
+    /// Normalizes format 1 (`80`) or format 2 (`77`) response
+    pub fn from_tlv(tlv: &Tlv) -> Result<GpoResponse> {
+        match (tlv.tag(), tlv.val()) {
+            (FORMAT1_TAG, Value::Val(v)) => {
+                if v.len() < 2 {
+                    return Err(TlvError::InvalidValue {
+                        tag_number: FORMAT1_TAG,
+                    });
+                }
+
+                Ok(GpoResponse {
+                    aip: Aip::from([v[0], v[1]]),
+                    afl: Afl::from_vec(&v[2..])?,
+                    tlvs: Vec::new(),
+                })
+            }
+            (FORMAT2_TAG, Value::TlvList(list)) => {
+                let aip = match find(list, AIP_TAG) {
+                    Some(Value::Val(v)) if v.len() == 2 => Aip::from([v[0], v[1]]),
+                    _ => {
+                        return Err(TlvError::InvalidValue {
+                            tag_number: AIP_TAG,
+                        })
+                    }
+                };
+
+                let afl = match find(list, AFL_TAG) {
+                    Some(val) => Afl::from_value(val)?,
+                    None => {
+                        return Err(TlvError::InvalidValue {
+                            tag_number: AFL_TAG,
+                        })
+                    }
+                };
+
+                let tlvs = list
+                    .iter()
+                    .filter(|x| x.tag() != AIP_TAG && x.tag() != AFL_TAG)
+                    .cloned()
+                    .collect();
+
+                Ok(GpoResponse { aip, afl, tlvs })
+            }
+            _ => Err(TlvError::InvalidTagNumber),
+        }
+    }
+
+    /// Returns Application Interchange Profile
+    pub fn aip(&self) -> Aip {
+        self.aip
+    }
+
+    /// Returns Application File Locator
+    pub fn afl(&self) -> &Afl {
+        &self.afl
+    }
+
+    /// Returns data objects other than AIP and AFL (always empty for format 1)
+    pub fn tlvs(&self) -> &[Tlv] {
+        &self.tlvs
+    }
+}
+
+/// Returns value of the first child with given tag
+pub(crate) fn find(list: &[Tlv], tag: usize) -> Option<&Value> {
+    list.iter().find(|x| x.tag() == tag).map(|x| x.val())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format1_test() {
+        // GPO response from Amex CDA trace
+        let gpo = GpoResponse::from_vec(&[
+            0x80, 0x16, 0x5D, 0x80, 0x08, 0x01, 0x01, 0x00, 0x08, 0x02, 0x02, 0x01, 0x08, 0x04,
+            0x05, 0x00, 0x10, 0x02, 0x03, 0x00, 0x18, 0x03, 0x03, 0x00,
+        ])
+        .unwrap();
+
+        assert_eq!(gpo.aip().bytes(), [0x5D, 0x80]);
+        assert_eq!(gpo.afl().entries().len(), 5);
+        assert!(gpo.tlvs().is_empty());
+
+        // no room for AIP
+        assert!(GpoResponse::from_vec(&[0x80, 0x01, 0x5D]).is_err());
+        // AFL is not multiple of 4
+        assert!(GpoResponse::from_vec(&[0x80, 0x03, 0x5D, 0x80, 0x08]).is_err());
+    }
+
+    #[test]
+    fn format2_test() {
+        let gpo = GpoResponse::from_vec(&[
+            0x77, 0x11, 0x82, 0x02, 0x19, 0x80, 0x94, 0x04, 0x08, 0x01, 0x01, 0x00, 0x9F, 0x36,
+            0x02, 0x00, 0x01, 0x57, 0x00,
+        ])
+        .unwrap();
+
+        assert_eq!(gpo.aip().bytes(), [0x19, 0x80]);
+        assert_eq!(gpo.afl().records(), [(1, 1)]);
+        assert_eq!(gpo.tlvs().len(), 2);
+        assert_eq!(gpo.tlvs()[0].tag(), 0x9F36);
+
+        // AFL is missing
+        assert!(GpoResponse::from_vec(&[0x77, 0x04, 0x82, 0x02, 0x19, 0x80]).is_err());
+        // unexpected template
+        assert!(GpoResponse::from_vec(&[0x70, 0x04, 0x82, 0x02, 0x19, 0x80]).is_err());
+    }
+}
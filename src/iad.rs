@@ -0,0 +1,508 @@
+//! Issuer Application Data (tag `9F10`) decoding.
+//!
+//! Content of IAD is defined by payment system: Visa VIS, Mastercard M/Chip, American Express
+//! AEIPS and EMV Common Core Definitions (CCD) use different layouts and Card Verification
+//! Results (CVR). [`IadRegistry`] tries registered [`IadDecoder`]s until one recognizes
+//! the format by its length and header bytes.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+pub use crate::gen_ac::IAD_TAG;
+
+/// Named part of Issuer Application Data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IadField {
+    pub name: &'static str,
+    pub value: Vec<u8>,
+}
+
+/// Structured breakdown of Issuer Application Data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedIad {
+    /// Name of recognized format
+    pub format: &'static str,
+    pub fields: Vec<IadField>,
+    /// Descriptions of Card Verification Results set bits and counters
+    pub cvr: Vec<String>,
+}
+
+impl DecodedIad {
+    /// Returns value of field with given name
+    pub fn field(&self, name: &str) -> Option<&[u8]> {
+        self.fields
+            .iter()
+            .find(|x| x.name == name)
+            .map(|x| &x.value[..])
+    }
+}
+
+impl fmt::Display for DecodedIad {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format)?;
+        for field in &self.fields {
+            write!(f, "\n{}: ", field.name)?;
+            for x in &field.value {
+                write!(f, "{:02X}", x)?;
+            }
+        }
+        for desc in &self.cvr {
+            write!(f, "\n  {}", desc)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decoder of payment system specific IAD format
+pub trait IadDecoder {
+    /// Returns breakdown of IAD or `None` if format is not recognized
+    fn decode(&self, iad: &[u8]) -> Option<DecodedIad>;
+}
+
+/// Ordered set of IAD decoders
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::iad::IadRegistry;
+/// let registry = IadRegistry::default();
+/// let iad = registry.decode(&[0x06, 0x01, 0x0A, 0x03, 0xA0, 0x00, 0x00]).unwrap();
+/// assert_eq!(iad.format, "Visa VIS");
+/// assert_eq!(iad.field("Cryptogram Version Number"), Some(&[0x0A][..]));
+/// assert_eq!(iad.cvr[0], "First GENERATE AC: ARQC");
+/// ```
+pub struct IadRegistry {
+    decoders: Vec<Box<dyn IadDecoder>>,
+}
+
+impl IadRegistry {
+    /// Returns registry without decoders
+    pub fn new() -> IadRegistry {
+        IadRegistry {
+            decoders: Vec::new(),
+        }
+    }
+
+    /// Adds decoder; it takes precedence over previously registered ones
+    pub fn register<D: IadDecoder + 'static>(&mut self, decoder: D) {
+        self.decoders.insert(0, Box::new(decoder));
+    }
+
+    /// Returns breakdown produced by the first decoder recognizing the format
+    pub fn decode(&self, iad: &[u8]) -> Option<DecodedIad> {
+        self.decoders.iter().find_map(|x| x.decode(iad))
+    }
+}
+
+impl Default for IadRegistry {
+    /// Returns registry with Mastercard, Amex, Visa and CCD decoders
+    fn default() -> IadRegistry {
+        let mut registry = IadRegistry::new();
+        registry.register(MastercardDecoder);
+        registry.register(AmexDecoder);
+        registry.register(VisaDecoder);
+        registry.register(CcdDecoder);
+        registry
+    }
+}
+
+/// Visa VIS format: length `06`, DKI, CVN (`0A`, `11` or `12`), CVR with length byte `03`,
+/// optionally followed by length-prefixed Issuer Discretionary Data
+pub struct VisaDecoder;
+
+impl IadDecoder for VisaDecoder {
+    fn decode(&self, iad: &[u8]) -> Option<DecodedIad> {
+        if ![0x0A, 0x11, 0x12].contains(iad.get(2)?) {
+            return None;
+        }
+        decode_vis("Visa VIS", iad)
+    }
+}
+
+/// American Express AEIPS format: same layout as Visa VIS with CVN `01` or `02`
+pub struct AmexDecoder;
+
+impl IadDecoder for AmexDecoder {
+    fn decode(&self, iad: &[u8]) -> Option<DecodedIad> {
+        if ![0x01, 0x02].contains(iad.get(2)?) {
+            return None;
+        }
+        decode_vis("Amex", iad)
+    }
+}
+
+/// Mastercard M/Chip format: DKI, CVN (`1x`), 6-byte CVR, DAC/IDN and counters
+pub struct MastercardDecoder;
+
+impl IadDecoder for MastercardDecoder {
+    fn decode(&self, iad: &[u8]) -> Option<DecodedIad> {
+        if ![18, 20, 26, 28].contains(&iad.len()) || iad[1] >> 4 != 0x01 {
+            return None;
+        }
+
+        let mut fields = vec_fields(&[
+            ("Derivation Key Index", &iad[0..1]),
+            ("Cryptogram Version Number", &iad[1..2]),
+            ("Card Verification Results", &iad[2..8]),
+            ("DAC/ICC Dynamic Number", &iad[8..10]),
+            ("Counters", &iad[10..18]),
+        ]);
+        if iad.len() > 18 {
+            fields.push(IadField {
+                name: "Issuer Discretionary Data",
+                value: iad[18..].to_vec(),
+            });
+        }
+
+        Some(DecodedIad {
+            format: "Mastercard M/Chip",
+            fields,
+            cvr: describe(&iad[2..8], &MCHIP_BITS, &MCHIP_COUNTERS),
+        })
+    }
+}
+
+/// EMV Common Core Definitions format A: length `0F`, CCI (`Ax`), DKI, 5-byte CVR,
+/// counters and length-prefixed Issuer Discretionary Data
+pub struct CcdDecoder;
+
+impl IadDecoder for CcdDecoder {
+    fn decode(&self, iad: &[u8]) -> Option<DecodedIad> {
+        if iad.len() != 32 || iad[0] != 0x0F || iad[1] >> 4 != 0x0A || iad[16] != 0x0F {
+            return None;
+        }
+
+        Some(DecodedIad {
+            format: "EMV CCD",
+            fields: vec_fields(&[
+                ("Common Core Identifier", &iad[1..2]),
+                ("Derivation Key Index", &iad[2..3]),
+                ("Card Verification Results", &iad[3..8]),
+                ("Counters", &iad[8..16]),
+                ("Issuer Discretionary Data", &iad[17..]),
+            ]),
+            cvr: describe(&iad[3..8], &CCD_BITS, &CCD_COUNTERS),
+        })
+    }
+}
+
+/// Set bit of CVR: byte index, mask and description
+type Bit = (usize, u8, &'static str);
+
+/// 4-bit counter of CVR: byte index, shift and description
+type Counter = (usize, u8, &'static str);
+
+const VIS_BITS: [Bit; 15] = [
+    (0, 0x08, "Issuer authentication performed and failed"),
+    (0, 0x04, "Offline PIN verification performed"),
+    (0, 0x02, "Offline PIN verification failed"),
+    (0, 0x01, "Unable to go online"),
+    (1, 0x80, "Last online transaction not completed"),
+    (1, 0x40, "PIN try limit exceeded"),
+    (1, 0x20, "Exceeded velocity checking counters"),
+    (1, 0x10, "New card"),
+    (
+        1,
+        0x08,
+        "Issuer authentication failure on last online transaction",
+    ),
+    (
+        1,
+        0x04,
+        "Issuer authentication not performed after online authorization",
+    ),
+    (
+        1,
+        0x02,
+        "Application blocked by card because PIN try limit exceeded",
+    ),
+    (
+        1,
+        0x01,
+        "Offline static data authentication failed on last transaction",
+    ),
+    (2, 0x08, "Issuer script processing failed"),
+    (
+        2,
+        0x04,
+        "Offline dynamic data authentication failed on last transaction",
+    ),
+    (2, 0x02, "Offline dynamic data authentication performed"),
+];
+
+const VIS_COUNTERS: [Counter; 1] = [(2, 4, "Number of issuer script commands")];
+
+const MCHIP_BITS: [Bit; 28] = [
+    (0, 0x08, "Date check failed"),
+    (0, 0x04, "Offline PIN verification performed"),
+    (0, 0x02, "Offline encrypted PIN verification performed"),
+    (0, 0x01, "Offline PIN verification successful"),
+    (1, 0x80, "DDA returned"),
+    (
+        1,
+        0x40,
+        "Combined DDA/AC generation returned in first GENERATE AC",
+    ),
+    (
+        1,
+        0x20,
+        "Combined DDA/AC generation returned in second GENERATE AC",
+    ),
+    (1, 0x10, "Issuer authentication performed"),
+    (1, 0x08, "CIAC-default skipped on CAT3"),
+    (1, 0x04, "Offline change PIN result"),
+    (3, 0x80, "Last online transaction not completed"),
+    (3, 0x40, "Unable to go online indicated"),
+    (3, 0x20, "Offline PIN verification not performed"),
+    (3, 0x10, "Offline PIN verification failed"),
+    (3, 0x08, "PIN try limit exceeded"),
+    (3, 0x04, "International transaction"),
+    (3, 0x02, "Domestic transaction"),
+    (3, 0x01, "Terminal erroneously considers offline PIN OK"),
+    (4, 0x80, "Lower consecutive offline limit exceeded"),
+    (4, 0x40, "Upper consecutive offline limit exceeded"),
+    (4, 0x20, "Lower cumulative offline limit exceeded"),
+    (4, 0x10, "Upper cumulative offline limit exceeded"),
+    (4, 0x08, "Go online on next transaction was set"),
+    (4, 0x04, "Issuer authentication failed"),
+    (4, 0x02, "Script received"),
+    (4, 0x01, "Script failed"),
+    (5, 0x02, "Match found in additional check table"),
+    (5, 0x01, "No match found in additional check table"),
+];
+
+const MCHIP_COUNTERS: [Counter; 2] = [(2, 4, "Script counter"), (2, 0, "PIN try counter")];
+
+const CCD_BITS: [Bit; 13] = [
+    (0, 0x08, "CDA performed"),
+    (0, 0x04, "Offline DDA performed"),
+    (0, 0x02, "Issuer authentication not performed"),
+    (0, 0x01, "Issuer authentication failed"),
+    (1, 0x08, "Offline PIN verification performed"),
+    (
+        1,
+        0x04,
+        "Offline PIN verification performed and PIN not successfully verified",
+    ),
+    (1, 0x02, "PIN try limit exceeded"),
+    (1, 0x01, "Last online transaction not completed"),
+    (2, 0x80, "Lower offline transaction count limit exceeded"),
+    (2, 0x40, "Upper offline transaction count limit exceeded"),
+    (2, 0x20, "Lower cumulative offline amount limit exceeded"),
+    (2, 0x10, "Upper cumulative offline amount limit exceeded"),
+    (3, 0x08, "Issuer script processing failed"),
+];
+
+const CCD_COUNTERS: [Counter; 2] = [
+    (1, 4, "PIN try counter"),
+    (3, 4, "Number of issuer script commands"),
+];
+
+/// Decodes layout shared by Visa VIS and Amex AEIPS
+fn decode_vis(format: &'static str, iad: &[u8]) -> Option<DecodedIad> {
+    if iad.len() < 7 || iad[0] != 0x06 || iad[3] != 0x03 {
+        return None;
+    }
+
+    let mut fields = vec_fields(&[
+        ("Derivation Key Index", &iad[1..2]),
+        ("Cryptogram Version Number", &iad[2..3]),
+        ("Card Verification Results", &iad[4..7]),
+    ]);
+
+    if iad.len() > 7 {
+        if iad[7] as usize != iad.len() - 8 {
+            return None;
+        }
+        fields.push(IadField {
+            name: "Issuer Discretionary Data",
+            value: iad[8..].to_vec(),
+        });
+    }
+
+    Some(DecodedIad {
+        format,
+        fields,
+        cvr: describe(&iad[4..7], &VIS_BITS, &VIS_COUNTERS),
+    })
+}
+
+fn vec_fields(fields: &[(&'static str, &[u8])]) -> Vec<IadField> {
+    fields
+        .iter()
+        .map(|&(name, value)| IadField {
+            name,
+            value: value.to_vec(),
+        })
+        .collect()
+}
+
+/// Describes CVR: types of cryptograms in bits 8-5 of the first byte, set bits
+/// and non-zero counters
+fn describe(cvr: &[u8], bits: &[Bit], counters: &[Counter]) -> Vec<String> {
+    let mut out = Vec::new();
+
+    let first = match (cvr[0] >> 4) & 0x03 {
+        0b00 => "AAC",
+        0b01 => "TC",
+        0b10 => "ARQC",
+        _ => "RFU",
+    };
+    out.push(format!("First GENERATE AC: {}", first));
+
+    let second = match cvr[0] >> 6 {
+        0b00 => "AAC",
+        0b01 => "TC",
+        0b10 => "Not requested",
+        _ => "RFU",
+    };
+    out.push(format!("Second GENERATE AC: {}", second));
+
+    out.extend(
+        bits.iter()
+            .filter(|&&(idx, mask, _)| cvr[idx] & mask != 0)
+            .map(|&(_, _, desc)| String::from(desc)),
+    );
+
+    out.extend(counters.iter().filter_map(|&(idx, shift, desc)| {
+        let n = (cvr[idx] >> shift) & 0x0F;
+        if n != 0 {
+            Some(format!("{}: {}", desc, n))
+        } else {
+            None
+        }
+    }));
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn amex_test() {
+        // IAD from Amex CDA trace
+        let iad = IadRegistry::default()
+            .decode(&[0x06, 0x01, 0x01, 0x03, 0xA0, 0x00, 0x00])
+            .unwrap();
+
+        assert_eq!(iad.format, "Amex");
+        assert_eq!(
+            iad.field("Card Verification Results"),
+            Some(&[0xA0, 0x00, 0x00][..])
+        );
+        assert_eq!(
+            iad.cvr,
+            [
+                "First GENERATE AC: ARQC",
+                "Second GENERATE AC: Not requested"
+            ]
+        );
+    }
+
+    #[test]
+    fn visa_test() {
+        let iad = IadRegistry::default()
+            .decode(&[0x06, 0x01, 0x12, 0x03, 0x64, 0x20, 0x32, 0x02, 0xAB, 0xCD])
+            .unwrap();
+
+        assert_eq!(iad.format, "Visa VIS");
+        assert_eq!(
+            iad.field("Issuer Discretionary Data"),
+            Some(&[0xAB, 0xCD][..])
+        );
+        assert_eq!(
+            iad.cvr,
+            [
+                "First GENERATE AC: ARQC",
+                "Second GENERATE AC: TC",
+                "Offline PIN verification performed",
+                "Exceeded velocity checking counters",
+                "Offline dynamic data authentication performed",
+                "Number of issuer script commands: 3",
+            ]
+        );
+
+        // wrong length of Issuer Discretionary Data
+        assert!(VisaDecoder
+            .decode(&[0x06, 0x01, 0x12, 0x03, 0x64, 0x20, 0x32, 0x03, 0xAB, 0xCD])
+            .is_none());
+    }
+
+    #[test]
+    fn mastercard_test() {
+        let iad = IadRegistry::default()
+            .decode(&[
+                0x01, 0x10, 0xA0, 0x40, 0x03, 0x82, 0x08, 0x00, 0x12, 0x34, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x00, 0xFF,
+            ])
+            .unwrap();
+
+        assert_eq!(iad.format, "Mastercard M/Chip");
+        assert_eq!(iad.field("DAC/ICC Dynamic Number"), Some(&[0x12, 0x34][..]));
+        assert_eq!(
+            iad.to_string(),
+            "Mastercard M/Chip\n\
+             Derivation Key Index: 01\n\
+             Cryptogram Version Number: 10\n\
+             Card Verification Results: A04003820800\n\
+             DAC/ICC Dynamic Number: 1234\n\
+             Counters: 00000000000000FF\n  \
+             First GENERATE AC: ARQC\n  \
+             Second GENERATE AC: Not requested\n  \
+             Combined DDA/AC generation returned in first GENERATE AC\n  \
+             Last online transaction not completed\n  \
+             Domestic transaction\n  \
+             Go online on next transaction was set\n  \
+             PIN try counter: 3"
+        );
+    }
+
+    #[test]
+    fn ccd_test() {
+        let mut data = [0; 32];
+        data[..8].copy_from_slice(&[0x0F, 0xA5, 0x01, 0x60, 0x38, 0x00, 0x00, 0x00]);
+        data[16] = 0x0F;
+
+        let iad = IadRegistry::default().decode(&data).unwrap();
+        assert_eq!(iad.format, "EMV CCD");
+        assert_eq!(iad.field("Common Core Identifier"), Some(&[0xA5][..]));
+        assert_eq!(
+            iad.cvr,
+            [
+                "First GENERATE AC: ARQC",
+                "Second GENERATE AC: TC",
+                "Offline PIN verification performed",
+                "PIN try counter: 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn registry_test() {
+        struct Custom;
+
+        impl IadDecoder for Custom {
+            fn decode(&self, iad: &[u8]) -> Option<DecodedIad> {
+                Some(DecodedIad {
+                    format: "Custom",
+                    fields: vec_fields(&[("Raw", iad)]),
+                    cvr: Vec::new(),
+                })
+            }
+        }
+
+        let iad = [0x06, 0x01, 0x0A, 0x03, 0xA0, 0x00, 0x00];
+        assert!(IadRegistry::new().decode(&iad).is_none());
+        assert!(IadRegistry::default().decode(&[0x01, 0x02]).is_none());
+
+        let mut registry = IadRegistry::default();
+        registry.register(Custom);
+        assert_eq!(registry.decode(&iad).unwrap().format, "Custom");
+    }
+}
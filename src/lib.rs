@@ -30,18 +30,101 @@
 
 extern crate alloc;
 
+pub mod afl;
+pub mod apdu;
+pub mod asn1;
+pub mod bitfield;
+#[cfg(feature = "cryptogram")]
+pub mod cryptogram;
+pub mod cvm;
+pub mod database;
+pub mod diff;
+pub mod directory;
+pub mod dol;
+pub mod gen_ac;
+pub mod gpo;
+pub mod iad;
+#[cfg(feature = "oda")]
+pub mod oda;
+pub mod oid;
+pub mod raw;
+pub mod schema;
 pub mod tlv;
+pub mod track2;
+#[cfg(feature = "x509")]
+pub mod x509;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::tlv::Tag;
 
 type Result<T> = core::result::Result<T, TlvError>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum TlvError {
     TruncatedTlv,
     InvalidLength,
     InvalidTagNumber,
-    TooShortBody { expected: usize, found: usize },
-    ValExpected { tag_number: usize },
+    TooShortBody {
+        expected: usize,
+        found: usize,
+    },
+    ValExpected {
+        tag_number: usize,
+    },
     TagPathError,
+    InvalidValue {
+        tag_number: usize,
+    },
+    DuplicateTag {
+        tag_number: usize,
+    },
+    UpdateNotAllowed {
+        tag_number: usize,
+    },
+    InvalidPem,
+    /// Length octet `80` (indefinite form) is not supported
+    IndefiniteLength,
+    /// Length octet `FF` is reserved
+    ReservedLength,
+    /// Input ends within length field
+    TruncatedLength,
+    /// Length is not encoded in the fewest possible octets
+    NonMinimalLength,
+    /// Length doesn't fit into `usize`
+    LengthOverflow,
+    /// Date or time field is out of range
+    InvalidDateTime,
+    /// Error of parsing data object at `offset` of input; `path` lists tags from
+    /// the outermost data object to the one being decoded. Error in header of
+    /// the outermost data object is not wrapped
+    Context {
+        path: Vec<Tag>,
+        offset: usize,
+        source: Box<TlvError>,
+    },
+}
+
+impl TlvError {
+    /// Returns underlying error stripped of context
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::tlv::Tlv;
+    /// # use tlv_parser::TlvError;
+    /// let err = Tlv::from_vec(&[0x70, 0x07, 0x5A, 0x01, 0x47, 0x9F, 0x46, 0x81, 0x80]).unwrap_err();
+    /// assert_eq!(err.to_string(), "in 70 / 9F46 at offset 0x05: Too short body: expected 128, found 0");
+    /// assert!(matches!(err.innermost(), TlvError::TooShortBody { expected: 128, found: 0 }));
+    /// ```
+    pub fn innermost(&self) -> &TlvError {
+        match self {
+            TlvError::Context { source, .. } => source.innermost(),
+            _ => self,
+        }
+    }
 }
 
 use core::fmt;
@@ -52,7 +135,7 @@ impl fmt::Display for TlvError {
 
         match self {
             TruncatedTlv => write!(f, "Too short input vector"),
-            InvalidLength => writeln!(f, "Invalid length value"),
+            InvalidLength => write!(f, "Invalid length value"),
             InvalidTagNumber => write!(f, "Invalid tag number"),
             TooShortBody { expected, found } => {
                 write!(f, "Too short body: expected {expected}, found {found}")
@@ -62,8 +145,45 @@ impl fmt::Display for TlvError {
                 "Tag number defines primitive TLV, but value is not Value::Val: {tag_number}"
             ),
             TagPathError => write!(f, "Provided 'tag-path' has error"),
+            InvalidValue { tag_number } => {
+                write!(f, "Invalid value of data object {tag_number:02X}")
+            }
+            DuplicateTag { tag_number } => write!(f, "Duplicate data object {tag_number:02X}"),
+            UpdateNotAllowed { tag_number } => {
+                write!(f, "Update of data object {tag_number:02X} is not allowed")
+            }
+            InvalidPem => write!(f, "Invalid PEM armor"),
+            IndefiniteLength => write!(f, "Indefinite length is not supported"),
+            ReservedLength => write!(f, "Reserved length octet FF"),
+            TruncatedLength => write!(f, "Truncated length field"),
+            NonMinimalLength => write!(f, "Length is not minimally encoded"),
+            LengthOverflow => write!(f, "Length is too big"),
+            InvalidDateTime => write!(f, "Invalid date or time"),
+            Context {
+                path,
+                offset,
+                source,
+            } => {
+                for (i, tag) in path.iter().enumerate() {
+                    match i {
+                        0 => write!(f, "in {tag:02X}")?,
+                        _ => write!(f, " / {tag:02X}")?,
+                    }
+                }
+                if !path.is_empty() {
+                    write!(f, " ")?;
+                }
+                write!(f, "at offset 0x{offset:02X}: {source}")
+            }
         }
     }
 }
 
-impl core::error::Error for TlvError {}
+impl core::error::Error for TlvError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            TlvError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,836 @@
+//! Offline Data Authentication: SDA, DDA and CDA.
+//!
+//! Implements recovery of Issuer and ICC public keys and verification of signed
+//! application data as defined in EMV 4.3 Book 2. Available with `oda` feature.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+
+use num_bigint::BigUint;
+use sha1::{Digest, Sha1};
+
+use crate::afl::Afl;
+use crate::bitfield::Tvr;
+use crate::dol::DolSource;
+use crate::tlv::{Tag, Tlv, Value};
+
+/// Tag number of Certification Authority Public Key Index
+pub const CA_KEY_INDEX_TAG: Tag = 0x8F;
+
+/// Tag number of Issuer Public Key Certificate
+pub const ISSUER_CERT_TAG: Tag = 0x90;
+
+/// Tag number of Issuer Public Key Remainder
+pub const ISSUER_REMAINDER_TAG: Tag = 0x92;
+
+/// Tag number of Issuer Public Key Exponent
+pub const ISSUER_EXPONENT_TAG: Tag = 0x9F32;
+
+/// Tag number of Signed Static Application Data
+pub const SSAD_TAG: Tag = 0x93;
+
+/// Tag number of ICC Public Key Certificate
+pub const ICC_CERT_TAG: Tag = 0x9F46;
+
+/// Tag number of ICC Public Key Exponent
+pub const ICC_EXPONENT_TAG: Tag = 0x9F47;
+
+/// Tag number of ICC Public Key Remainder
+pub const ICC_REMAINDER_TAG: Tag = 0x9F48;
+
+/// Tag number of Static Data Authentication Tag List
+pub const SDA_TAG_LIST_TAG: Tag = 0x9F4A;
+
+/// Tag number of Signed Dynamic Application Data
+pub const SDAD_TAG: Tag = 0x9F4B;
+
+const PAN_TAG: Tag = 0x5A;
+const AIP_TAG: Tag = 0x82;
+
+/// Length of SHA-1 hash
+const HASH_LEN: usize = 20;
+
+type Result<T> = core::result::Result<T, OdaFailure>;
+
+/// Method of offline data authentication
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OdaMethod {
+    Sda,
+    Dda,
+    Cda,
+}
+
+/// Reason of offline data authentication failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OdaFailure {
+    /// Data object required for authentication is missing
+    MissingData { tag_number: Tag },
+    /// Certification Authority public key with given RID and index is unknown
+    CaKeyNotFound,
+    /// Length of certificate or signature differs from length of key modulus
+    InvalidLength { tag_number: Tag },
+    /// Recovered data has wrong header, format or trailer
+    InvalidFormat { tag_number: Tag },
+    /// Hash algorithm or public key algorithm is not supported
+    UnsupportedAlgorithm { tag_number: Tag },
+    /// Hash of recovered data does not match
+    HashMismatch { tag_number: Tag },
+    /// Issuer identifier or PAN in certificate does not match PAN of application
+    PanMismatch { tag_number: Tag },
+    /// Certificate is expired
+    Expired { tag_number: Tag },
+    /// Record to authenticate is missing or is not coded as template `70`,
+    /// or SDA Tag List contains tags other than `82`
+    InvalidStaticData,
+    /// CID in Signed Dynamic Application Data differs from CID returned by card
+    CidMismatch,
+    /// Transaction Data Hash Code does not match
+    TransactionDataMismatch,
+}
+
+impl OdaFailure {
+    /// Sets TVR bits corresponding to failure of given method
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::bitfield::Tvr;
+    /// # use tlv_parser::oda::*;
+    /// let mut tvr = Tvr::new();
+    /// OdaFailure::MissingData { tag_number: 0x9F46 }.update_tvr(OdaMethod::Cda, &mut tvr);
+    /// assert!(tvr.icc_data_missing());
+    /// assert!(tvr.cda_failed());
+    /// ```
+    pub fn update_tvr(&self, method: OdaMethod, tvr: &mut Tvr) {
+        if let OdaFailure::MissingData { .. } = *self {
+            tvr.set_icc_data_missing(true);
+        }
+
+        match method {
+            OdaMethod::Sda => tvr.set_sda_failed(true),
+            OdaMethod::Dda => tvr.set_dda_failed(true),
+            OdaMethod::Cda => tvr.set_cda_failed(true),
+        }
+    }
+}
+
+impl fmt::Display for OdaFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use OdaFailure::*;
+
+        match self {
+            MissingData { tag_number } => write!(f, "Missing data object {tag_number:02X}"),
+            CaKeyNotFound => write!(f, "Certification Authority public key not found"),
+            InvalidLength { tag_number } => write!(f, "Invalid length of {tag_number:02X}"),
+            InvalidFormat { tag_number } => {
+                write!(f, "Invalid format of data recovered from {tag_number:02X}")
+            }
+            UnsupportedAlgorithm { tag_number } => {
+                write!(f, "Unsupported algorithm in {tag_number:02X}")
+            }
+            HashMismatch { tag_number } => write!(f, "Hash mismatch in {tag_number:02X}"),
+            PanMismatch { tag_number } => write!(f, "PAN mismatch in {tag_number:02X}"),
+            Expired { tag_number } => write!(f, "Certificate {tag_number:02X} is expired"),
+            InvalidStaticData => write!(f, "Invalid static data to be authenticated"),
+            CidMismatch => write!(f, "Cryptogram Information Data mismatch"),
+            TransactionDataMismatch => write!(f, "Transaction Data Hash Code mismatch"),
+        }
+    }
+}
+
+/// RSA public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub modulus: Vec<u8>,
+    pub exponent: Vec<u8>,
+}
+
+impl PublicKey {
+    /// Creates public key from modulus and exponent
+    pub fn new(modulus: Vec<u8>, exponent: Vec<u8>) -> PublicKey {
+        PublicKey { modulus, exponent }
+    }
+
+    /// Recovers data signed with corresponding private key and checks header and trailer
+    fn recover(&self, tag: Tag, data: &[u8]) -> Result<Vec<u8>> {
+        let len = self.modulus.len();
+        if data.len() != len {
+            return Err(OdaFailure::InvalidLength { tag_number: tag });
+        }
+
+        let modulus = BigUint::from_bytes_be(&self.modulus);
+        let data = BigUint::from_bytes_be(data);
+        if data >= modulus {
+            return Err(OdaFailure::InvalidFormat { tag_number: tag });
+        }
+
+        let recovered = data
+            .modpow(&BigUint::from_bytes_be(&self.exponent), &modulus)
+            .to_bytes_be();
+
+        let mut out = Vec::with_capacity(len);
+        out.resize(len - recovered.len(), 0);
+        out.extend_from_slice(&recovered);
+
+        if out[0] != 0x6A || out[len - 1] != 0xBC {
+            return Err(OdaFailure::InvalidFormat { tag_number: tag });
+        }
+
+        Ok(out)
+    }
+}
+
+/// Certification Authority public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaPublicKey {
+    /// Registered Application Provider Identifier
+    pub rid: [u8; 5],
+    /// Certification Authority Public Key Index
+    pub index: u8,
+    pub key: PublicKey,
+}
+
+/// Data recovered from Signed Dynamic Application Data generated for CDA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CdaData {
+    pub icc_dynamic_number: Vec<u8>,
+    pub cid: u8,
+    pub ac: [u8; 8],
+}
+
+/// Builds static data to be authenticated
+///
+/// `records` maps `(sfi, record)` to READ RECORD response data (without status word),
+/// `card` provides SDA Tag List and AIP.
+pub fn static_data<S>(afl: &Afl, records: &BTreeMap<(u8, u8), Vec<u8>>, card: &S) -> Result<Vec<u8>>
+where
+    S: DolSource + ?Sized,
+{
+    let mut out = Vec::new();
+
+    for (sfi, record) in afl.oda_records() {
+        let raw = records
+            .get(&(sfi, record))
+            .ok_or(OdaFailure::InvalidStaticData)?;
+
+        if sfi > 10 {
+            out.extend_from_slice(raw);
+            continue;
+        }
+
+        // for SFI 1-10 only value of template '70' is authenticated
+        let iter = &mut raw.iter();
+        let tag = Tlv::read_tag(iter).map_err(|_| OdaFailure::InvalidStaticData)?;
+        if tag != 0x70 {
+            return Err(OdaFailure::InvalidStaticData);
+        }
+
+        let len = Tlv::read_len(iter).map_err(|_| OdaFailure::InvalidStaticData)?;
+        let start = raw.len() - iter.len();
+        out.extend_from_slice(&raw[start..start + len]);
+    }
+
+    if let Some(list) = card.dol_value(SDA_TAG_LIST_TAG) {
+        let tags = Tlv::parse_tag_list(list).map_err(|_| OdaFailure::InvalidStaticData)?;
+        for tag in tags {
+            if tag != AIP_TAG {
+                return Err(OdaFailure::InvalidStaticData);
+            }
+            out.extend_from_slice(get(card, AIP_TAG)?);
+        }
+    }
+
+    Ok(out)
+}
+
+/// Recovers Issuer public key from Issuer Public Key Certificate
+///
+/// RID is taken from the first 5 bytes of `aid`. `today` is Transaction Date (`9A`,
+/// `YYMMDD`), expiration of certificate is not checked if it is `None`.
+pub fn recover_issuer_key<S>(
+    ca_keys: &[CaPublicKey],
+    aid: &[u8],
+    card: &S,
+    today: Option<&[u8]>,
+) -> Result<PublicKey>
+where
+    S: DolSource + ?Sized,
+{
+    let index = get(card, CA_KEY_INDEX_TAG)?;
+    let ca = ca_keys
+        .iter()
+        .find(|k| aid.get(..5) == Some(&k.rid[..]) && index == [k.index])
+        .ok_or(OdaFailure::CaKeyNotFound)?;
+
+    let cert = get(card, ISSUER_CERT_TAG)?;
+    let exponent = get(card, ISSUER_EXPONENT_TAG)?;
+    let remainder = card.dol_value(ISSUER_REMAINDER_TAG).unwrap_or(&[]);
+
+    let rec = ca.key.recover(ISSUER_CERT_TAG, cert)?;
+    if rec.len() < 36 + HASH_LEN {
+        return Err(OdaFailure::InvalidLength {
+            tag_number: ISSUER_CERT_TAG,
+        });
+    }
+
+    if rec[1] != 0x02 {
+        return Err(OdaFailure::InvalidFormat {
+            tag_number: ISSUER_CERT_TAG,
+        });
+    }
+
+    check_algorithms(rec[11], rec[12], ISSUER_CERT_TAG)?;
+    check_hash(&rec, &[remainder, exponent], ISSUER_CERT_TAG)?;
+
+    // issuer identifier is the leftmost 3-8 digits of PAN
+    let pan = digits(get(card, PAN_TAG)?);
+    let id = digits(&rec[2..6]);
+    if id.len() < 3 || !pan.starts_with(&id) {
+        return Err(OdaFailure::PanMismatch {
+            tag_number: ISSUER_CERT_TAG,
+        });
+    }
+
+    check_expiry(&rec[6..8], today, ISSUER_CERT_TAG)?;
+
+    let modulus = join_modulus(&rec, 15, rec[13] as usize, remainder, ISSUER_REMAINDER_TAG)?;
+    Ok(PublicKey::new(modulus, exponent.to_vec()))
+}
+
+/// Recovers ICC public key from ICC Public Key Certificate
+///
+/// `static_data` is the result of [`static_data`], `today` has the same meaning
+/// as in [`recover_issuer_key`].
+pub fn recover_icc_key<S>(
+    issuer: &PublicKey,
+    card: &S,
+    static_data: &[u8],
+    today: Option<&[u8]>,
+) -> Result<PublicKey>
+where
+    S: DolSource + ?Sized,
+{
+    let cert = get(card, ICC_CERT_TAG)?;
+    let exponent = get(card, ICC_EXPONENT_TAG)?;
+    let remainder = card.dol_value(ICC_REMAINDER_TAG).unwrap_or(&[]);
+
+    let rec = issuer.recover(ICC_CERT_TAG, cert)?;
+    if rec.len() < 42 + HASH_LEN {
+        return Err(OdaFailure::InvalidLength {
+            tag_number: ICC_CERT_TAG,
+        });
+    }
+
+    if rec[1] != 0x04 {
+        return Err(OdaFailure::InvalidFormat {
+            tag_number: ICC_CERT_TAG,
+        });
+    }
+
+    check_algorithms(rec[17], rec[18], ICC_CERT_TAG)?;
+    check_hash(&rec, &[remainder, exponent, static_data], ICC_CERT_TAG)?;
+
+    if digits(&rec[2..12]) != digits(get(card, PAN_TAG)?) {
+        return Err(OdaFailure::PanMismatch {
+            tag_number: ICC_CERT_TAG,
+        });
+    }
+
+    check_expiry(&rec[12..14], today, ICC_CERT_TAG)?;
+
+    let modulus = join_modulus(&rec, 21, rec[19] as usize, remainder, ICC_REMAINDER_TAG)?;
+    Ok(PublicKey::new(modulus, exponent.to_vec()))
+}
+
+/// Verifies Signed Static Application Data and returns Data Authentication Code
+pub fn verify_sda<S>(issuer: &PublicKey, card: &S, static_data: &[u8]) -> Result<[u8; 2]>
+where
+    S: DolSource + ?Sized,
+{
+    let rec = issuer.recover(SSAD_TAG, get(card, SSAD_TAG)?)?;
+    if rec.len() < 5 + HASH_LEN + 1 {
+        return Err(OdaFailure::InvalidLength {
+            tag_number: SSAD_TAG,
+        });
+    }
+
+    if rec[1] != 0x03 {
+        return Err(OdaFailure::InvalidFormat {
+            tag_number: SSAD_TAG,
+        });
+    }
+
+    check_algorithms(rec[2], 0x01, SSAD_TAG)?;
+    check_hash(&rec, &[static_data], SSAD_TAG)?;
+
+    Ok([rec[3], rec[4]])
+}
+
+/// Verifies Signed Dynamic Application Data returned by INTERNAL AUTHENTICATE
+/// and returns ICC Dynamic Data
+///
+/// `ddol_data` is DDOL-related data sent to card.
+pub fn verify_dda(icc: &PublicKey, sdad: &[u8], ddol_data: &[u8]) -> Result<Vec<u8>> {
+    let rec = icc.recover(SDAD_TAG, sdad)?;
+    if rec.len() < 4 + HASH_LEN + 1 {
+        return Err(OdaFailure::InvalidLength {
+            tag_number: SDAD_TAG,
+        });
+    }
+
+    if rec[1] != 0x05 {
+        return Err(OdaFailure::InvalidFormat {
+            tag_number: SDAD_TAG,
+        });
+    }
+
+    check_algorithms(rec[2], 0x01, SDAD_TAG)?;
+
+    let len = rec[3] as usize;
+    if 4 + len > rec.len() - HASH_LEN - 1 {
+        return Err(OdaFailure::InvalidFormat {
+            tag_number: SDAD_TAG,
+        });
+    }
+
+    check_hash(&rec, &[ddol_data], SDAD_TAG)?;
+
+    Ok(rec[4..4 + len].to_vec())
+}
+
+/// Verifies Signed Dynamic Application Data returned by GENERATE AC
+///
+/// `cid` is Cryptogram Information Data returned by card and `transaction_data`
+/// is the result of [`cda_transaction_data`].
+pub fn verify_cda(
+    icc: &PublicKey,
+    sdad: &[u8],
+    unpredictable_number: &[u8],
+    cid: u8,
+    transaction_data: &[u8],
+) -> Result<CdaData> {
+    let data = verify_dda(icc, sdad, unpredictable_number)?;
+
+    let invalid = OdaFailure::InvalidFormat {
+        tag_number: SDAD_TAG,
+    };
+
+    let len = *data.first().ok_or(invalid)? as usize;
+    if data.len() < 1 + len + 1 + 8 + HASH_LEN {
+        return Err(invalid);
+    }
+
+    let (number, rest) = data[1..].split_at(len);
+    if rest[0] != cid {
+        return Err(OdaFailure::CidMismatch);
+    }
+
+    if Sha1::digest(transaction_data)[..] != rest[9..9 + HASH_LEN] {
+        return Err(OdaFailure::TransactionDataMismatch);
+    }
+
+    let mut ac = [0; 8];
+    ac.copy_from_slice(&rest[1..9]);
+
+    Ok(CdaData {
+        icc_dynamic_number: number.to_vec(),
+        cid: rest[0],
+        ac,
+    })
+}
+
+/// Builds input of Transaction Data Hash Code for CDA
+///
+/// `response` is format 2 response to GENERATE AC; its data objects except
+/// Signed Dynamic Application Data are appended in the order they were returned.
+pub fn cda_transaction_data(
+    pdol_data: &[u8],
+    cdol1_data: &[u8],
+    cdol2_data: Option<&[u8]>,
+    response: &Tlv,
+) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(pdol_data);
+    out.extend_from_slice(cdol1_data);
+    out.extend_from_slice(cdol2_data.unwrap_or(&[]));
+
+    if let Value::TlvList(ref list) = *response.val() {
+        for tlv in list.iter().filter(|x| x.tag() != SDAD_TAG) {
+            out.extend(tlv.to_vec());
+        }
+    }
+
+    out
+}
+
+fn get<S: DolSource + ?Sized>(card: &S, tag: Tag) -> Result<&[u8]> {
+    card.dol_value(tag)
+        .ok_or(OdaFailure::MissingData { tag_number: tag })
+}
+
+/// Checks that SHA-1 and RSA are indicated
+fn check_algorithms(hash: u8, key: u8, tag: Tag) -> Result<()> {
+    if hash != 0x01 || key != 0x01 {
+        return Err(OdaFailure::UnsupportedAlgorithm { tag_number: tag });
+    }
+    Ok(())
+}
+
+/// Compares hash stored before trailer with hash of recovered data and extra input
+fn check_hash(rec: &[u8], extra: &[&[u8]], tag: Tag) -> Result<()> {
+    let end = rec.len() - HASH_LEN - 1;
+
+    let mut hasher = Sha1::new();
+    hasher.update(&rec[1..end]);
+    for x in extra {
+        hasher.update(x);
+    }
+
+    if hasher.finalize()[..] != rec[end..end + HASH_LEN] {
+        return Err(OdaFailure::HashMismatch { tag_number: tag });
+    }
+    Ok(())
+}
+
+/// Checks certificate expiration date (`MMYY`) against transaction date (`YYMMDD`)
+fn check_expiry(expiry: &[u8], today: Option<&[u8]>, tag: Tag) -> Result<()> {
+    if let Some(&[yy, mm, ..]) = today {
+        let cert = (year(bcd(expiry[1])), bcd(expiry[0]));
+        if cert < (year(bcd(yy)), bcd(mm)) {
+            return Err(OdaFailure::Expired { tag_number: tag });
+        }
+    }
+    Ok(())
+}
+
+/// Concatenates leftmost digits of public key from certificate with remainder
+fn join_modulus(
+    rec: &[u8],
+    start: usize,
+    len: usize,
+    remainder: &[u8],
+    remainder_tag: Tag,
+) -> Result<Vec<u8>> {
+    let room = rec.len() - HASH_LEN - 1 - start;
+    if len <= room {
+        return Ok(rec[start..start + len].to_vec());
+    }
+
+    if remainder.is_empty() {
+        return Err(OdaFailure::MissingData {
+            tag_number: remainder_tag,
+        });
+    }
+
+    if remainder.len() != len - room {
+        return Err(OdaFailure::InvalidLength {
+            tag_number: remainder_tag,
+        });
+    }
+
+    let mut modulus = rec[start..start + room].to_vec();
+    modulus.extend_from_slice(remainder);
+    Ok(modulus)
+}
+
+/// Returns digits of compressed numeric value
+fn digits(cn: &[u8]) -> Vec<u8> {
+    cn.iter()
+        .flat_map(|x| [x >> 4, x & 0x0F])
+        .take_while(|&x| x != 0x0F)
+        .collect()
+}
+
+fn bcd(x: u8) -> u16 {
+    (x >> 4) as u16 * 10 + (x & 0x0F) as u16
+}
+
+fn year(yy: u16) -> u16 {
+    if yy < 50 {
+        2000 + yy
+    } else {
+        1900 + yy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const CA_N: &str = "CDFA9A5922778614D1A0B14CE4CFBC5FC84575F8C868B88B0478BC25B4DFE9329048879616A51AD573674326612ECE2C147D9802086078522E13931B43171796A85A328783324886F05D9272E3E79A2255613FE9B9C2E4A24178BE9B2F5D0AA179F75C64B5A9A8890CBBA99914604E446A7EFD1B0A8699F11C2A8DAD03B57B23";
+    const CA_D: &str = "8951BC3B6C4FAEB88BC076334335283FDAD8F950859B25B202FB2819233FF0CC60305A640F18BC8E4CEF8219961F341D62FE6556B0405036C96262122CBA0FB8923520C42C3AF7AEA0042EEB6C3D002BC4A2D097E6A792C2F73A69DE20209B4247A64F13D385648A59152179778737C0147DEFCF4305BC1B1241D3CB7862084B";
+    const ISSUER_N: &str = "D1D310D30162A4733C0C9EF613326956DA67E5B5F88A8797896FB669639AF83F2F0BF3D78D1522CEB2E6BBE255A6A70756B07504014F6BDCFE10CD4BA59220E2FE122D2F9EA6338D03861AD69F25886DEA4CBE04ADC59F56B2104FB13D828103AD402700A49E667314F1F3F7412FC389";
+    const ISSUER_D: &str = "8BE20B375641C2F77D5DBF4EB776F0E4919A9923FB07050FB0F524464267502A1F5D4D3A5E0E173477447D418E6F1A04E475A35800DF9D3CC9B078BBF5A8A93DADCB6573FB92B0118AFE6CBDAEEAF0F50CBA801F8FB637F838C0BD3D3A73268B0F97F9B6BB8DBA54B04AD1ED4A724C63";
+    const ICC_N: &str = "DD1897ADB6F5133E9BA5F062F16203FF75CC573169A29B6E270670A1CDDAE10DF049A986E67C94EF2827780DC067FB754DA82CE3A8C63B5D2D6513E6C7AD1E903401A23B5087B4CA9397F39DC8A77C68FEC58962857D5C85E054D1EC66E701A9";
+    const ICC_D: &str = "9365BA73CF4E0CD467C3F5974B96AD54F932E4CB9BC1BCF41A044B16893C96094ADBC659EEFDB89F701A50092AEFFCF7A08EE755BC45A64BBBEF96F07778B0C40754C4F3FE053B5550FAF4454717AF1BEABFA972EAF1BD028C62AC79C28B5FE3";
+
+    const AID: [u8; 7] = [0xA0, 0x00, 0x00, 0x00, 0x25, 0x01, 0x04];
+    const PAN: [u8; 8] = [0x37, 0x42, 0x45, 0x00, 0x17, 0x41, 0x00, 0x7F];
+    const UN: [u8; 4] = [0x11, 0x22, 0x33, 0x44];
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Appends hash and trailer to data and signs it with private key
+    fn sign(n: &str, d: &str, mut data: Vec<u8>, extra: &[&[u8]]) -> Vec<u8> {
+        let len = n.len() / 2;
+        data.resize(len - HASH_LEN - 1, 0xBB);
+
+        let mut hasher = Sha1::new();
+        hasher.update(&data[1..]);
+        for x in extra {
+            hasher.update(x);
+        }
+        data.extend_from_slice(&hasher.finalize());
+        data.push(0xBC);
+
+        let signed = BigUint::from_bytes_be(&data)
+            .modpow(
+                &BigUint::from_bytes_be(&hex(d)),
+                &BigUint::from_bytes_be(&hex(n)),
+            )
+            .to_bytes_be();
+        let mut out = vec![0; len - signed.len()];
+        out.extend_from_slice(&signed);
+        out
+    }
+
+    fn tlv(tag: Tag, val: Vec<u8>) -> Tlv {
+        Tlv::new(tag, Value::Val(val)).unwrap()
+    }
+
+    fn ca_keys() -> Vec<CaPublicKey> {
+        vec![CaPublicKey {
+            rid: [0xA0, 0x00, 0x00, 0x00, 0x25],
+            index: 0xA1,
+            key: PublicKey::new(hex(CA_N), vec![0x03]),
+        }]
+    }
+
+    fn records() -> BTreeMap<(u8, u8), Vec<u8>> {
+        let mut records = BTreeMap::new();
+        let mut record = vec![0x70, 0x0A, 0x5A, 0x08];
+        record.extend_from_slice(&PAN);
+        records.insert((1, 1), record);
+        records.insert((1, 2), vec![0x70, 0x03, 0x5F, 0x34, 0x00]);
+        records.insert((11, 1), vec![0x70, 0x04, 0x5F, 0x34, 0x01, 0x00]);
+        records
+    }
+
+    fn afl() -> Afl {
+        Afl::from_vec(&[0x08, 0x01, 0x02, 0x01, 0x58, 0x01, 0x01, 0x01]).unwrap()
+    }
+
+    fn card(static_data: &[u8]) -> Vec<Tlv> {
+        let issuer_n = hex(ISSUER_N);
+        let icc_n = hex(ICC_N);
+
+        let mut issuer_cert = vec![
+            0x6A, 0x02, 0x37, 0x42, 0x45, 0xFF, 0x12, 0x30, 0x00, 0x00, 0x01, 0x01, 0x01, 0x70,
+            0x01,
+        ];
+        issuer_cert.extend_from_slice(&issuer_n[..92]);
+        let issuer_cert = sign(CA_N, CA_D, issuer_cert, &[&issuer_n[92..], &[0x03]]);
+
+        let mut icc_cert = vec![0x6A, 0x04];
+        icc_cert.extend_from_slice(&PAN);
+        icc_cert.extend_from_slice(&[
+            0xFF, 0xFF, 0x12, 0x30, 0x00, 0x00, 0x02, 0x01, 0x01, 0x60, 0x01,
+        ]);
+        icc_cert.extend_from_slice(&icc_n[..70]);
+        let icc_cert = sign(
+            ISSUER_N,
+            ISSUER_D,
+            icc_cert,
+            &[&icc_n[70..], &[0x03], static_data],
+        );
+
+        let ssad = sign(
+            ISSUER_N,
+            ISSUER_D,
+            vec![0x6A, 0x03, 0x01, 0xDA, 0xC1],
+            &[static_data],
+        );
+
+        vec![
+            tlv(0x8F, vec![0xA1]),
+            tlv(0x90, issuer_cert),
+            tlv(0x92, issuer_n[92..].to_vec()),
+            tlv(0x9F32, vec![0x03]),
+            tlv(0x5A, PAN.to_vec()),
+            tlv(0x9F46, icc_cert),
+            tlv(0x9F47, vec![0x03]),
+            tlv(0x9F48, icc_n[70..].to_vec()),
+            tlv(0x93, ssad),
+            tlv(0x82, vec![0x5D, 0x80]),
+            tlv(0x9F4A, vec![0x82]),
+        ]
+    }
+
+    #[test]
+    fn static_data_test() {
+        let card = [tlv(0x82, vec![0x5D, 0x80]), tlv(0x9F4A, vec![0x82])];
+        let data = static_data(&afl(), &records(), &card[..]).unwrap();
+
+        let mut expected = vec![0x5A, 0x08];
+        expected.extend_from_slice(&PAN);
+        expected.extend_from_slice(&[0x70, 0x04, 0x5F, 0x34, 0x01, 0x00, 0x5D, 0x80]);
+        assert_eq!(data, expected);
+
+        let mut records = records();
+        records.insert((1, 1), vec![0x77, 0x03, 0x5F, 0x34, 0x00]);
+        assert_eq!(
+            static_data(&afl(), &records, &card[..]),
+            Err(OdaFailure::InvalidStaticData)
+        );
+
+        let card = [tlv(0x9F4A, vec![0x9F, 0x36])];
+        assert_eq!(
+            static_data(&afl(), &self::records(), &card[..]),
+            Err(OdaFailure::InvalidStaticData)
+        );
+    }
+
+    #[test]
+    fn sda_test() {
+        let static_data = static_data(&afl(), &records(), &card(&[])[..]).unwrap();
+        let card = card(&static_data);
+
+        let issuer =
+            recover_issuer_key(&ca_keys(), &AID, &card[..], Some(&[0x24, 0x10, 0x19])).unwrap();
+        assert_eq!(issuer.modulus, hex(ISSUER_N));
+        assert_eq!(issuer.exponent, [0x03]);
+
+        assert_eq!(
+            verify_sda(&issuer, &card[..], &static_data),
+            Ok([0xDA, 0xC1])
+        );
+        assert_eq!(
+            verify_sda(&issuer, &card[..], &static_data[1..]),
+            Err(OdaFailure::HashMismatch {
+                tag_number: SSAD_TAG
+            })
+        );
+    }
+
+    #[test]
+    fn dda_test() {
+        let static_data = static_data(&afl(), &records(), &card(&[])[..]).unwrap();
+        let card = card(&static_data);
+
+        let issuer = recover_issuer_key(&ca_keys(), &AID, &card[..], None).unwrap();
+        let icc = recover_icc_key(&issuer, &card[..], &static_data, None).unwrap();
+        assert_eq!(icc.modulus, hex(ICC_N));
+
+        let dynamic_data = vec![0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+        let mut sdad = vec![0x6A, 0x05, 0x01, dynamic_data.len() as u8];
+        sdad.extend_from_slice(&dynamic_data);
+        let sdad = sign(ICC_N, ICC_D, sdad, &[&UN]);
+
+        assert_eq!(verify_dda(&icc, &sdad, &UN), Ok(dynamic_data));
+        assert_eq!(
+            verify_dda(&icc, &sdad, &[0x11, 0x22, 0x33, 0x45]),
+            Err(OdaFailure::HashMismatch {
+                tag_number: SDAD_TAG
+            })
+        );
+        assert_eq!(
+            verify_dda(&icc, &sdad[1..], &UN),
+            Err(OdaFailure::InvalidLength {
+                tag_number: SDAD_TAG
+            })
+        );
+    }
+
+    #[test]
+    fn cda_test() {
+        let static_data = static_data(&afl(), &records(), &card(&[])[..]).unwrap();
+        let card = card(&static_data);
+
+        let issuer = recover_issuer_key(&ca_keys(), &AID, &card[..], None).unwrap();
+        let icc = recover_icc_key(&issuer, &card[..], &static_data, None).unwrap();
+
+        let response = Tlv::new(
+            0x77,
+            Value::TlvList(vec![
+                tlv(0x9F27, vec![0x80]),
+                tlv(0x9F36, vec![0x00, 0x06]),
+                tlv(0x9F10, vec![0x06, 0x01, 0x01, 0x03, 0xA0, 0x00, 0x00]),
+            ]),
+        )
+        .unwrap();
+        let transaction_data = cda_transaction_data(&[0x22], &[0x00, 0x01], None, &response);
+        assert_eq!(transaction_data.len(), 3 + 4 + 5 + 10);
+
+        let mut dynamic_data = vec![0x02, 0xAB, 0xCD, 0x80];
+        dynamic_data.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        dynamic_data.extend_from_slice(&Sha1::digest(&transaction_data));
+
+        let mut sdad = vec![0x6A, 0x05, 0x01, dynamic_data.len() as u8];
+        sdad.extend_from_slice(&dynamic_data);
+        let sdad = sign(ICC_N, ICC_D, sdad, &[&UN]);
+
+        let cda = verify_cda(&icc, &sdad, &UN, 0x80, &transaction_data).unwrap();
+        assert_eq!(cda.icc_dynamic_number, [0xAB, 0xCD]);
+        assert_eq!(cda.ac, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        assert_eq!(
+            verify_cda(&icc, &sdad, &UN, 0x40, &transaction_data),
+            Err(OdaFailure::CidMismatch)
+        );
+        assert_eq!(
+            verify_cda(&icc, &sdad, &UN, 0x80, &transaction_data[1..]),
+            Err(OdaFailure::TransactionDataMismatch)
+        );
+    }
+
+    #[test]
+    fn failures_test() {
+        let static_data = static_data(&afl(), &records(), &card(&[])[..]).unwrap();
+        let card = card(&static_data);
+
+        let mut keys = ca_keys();
+        keys[0].index = 0xA2;
+        assert_eq!(
+            recover_issuer_key(&keys, &AID, &card[..], None),
+            Err(OdaFailure::CaKeyNotFound)
+        );
+
+        // certificates expire in December 2030
+        assert!(recover_issuer_key(&ca_keys(), &AID, &card[..], Some(&[0x30, 0x12, 0x31])).is_ok());
+        assert_eq!(
+            recover_issuer_key(&ca_keys(), &AID, &card[..], Some(&[0x31, 0x01, 0x01])),
+            Err(OdaFailure::Expired {
+                tag_number: ISSUER_CERT_TAG
+            })
+        );
+
+        let issuer = recover_issuer_key(&ca_keys(), &AID, &card[..], None).unwrap();
+        assert_eq!(
+            recover_icc_key(&issuer, &card[..], &static_data[1..], None),
+            Err(OdaFailure::HashMismatch {
+                tag_number: ICC_CERT_TAG
+            })
+        );
+
+        let mut card = card;
+        card.retain(|x| x.tag() != ISSUER_EXPONENT_TAG);
+        let failure = recover_issuer_key(&ca_keys(), &AID, &card[..], None).unwrap_err();
+        assert_eq!(
+            failure,
+            OdaFailure::MissingData {
+                tag_number: ISSUER_EXPONENT_TAG
+            }
+        );
+
+        let mut tvr = Tvr::new();
+        failure.update_tvr(OdaMethod::Dda, &mut tvr);
+        assert_eq!(tvr.bytes(), [0x28, 0x00, 0x00, 0x00, 0x00]);
+    }
+}
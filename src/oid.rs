@@ -0,0 +1,305 @@
+//! Object identifiers (ASN.1 universal tag `06`).
+//!
+//! Arcs are encoded in base-128 as specified in ITU-T X.690, section 8.19.
+//! Names of common algorithms, X.500 attributes and certificate extensions are
+//! available with `oid-registry` feature.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::asn1::OID_TAG;
+use crate::tlv::{Tlv, Value};
+use crate::{Result, TlvError};
+
+pub const RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+pub const SHA1_WITH_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 5];
+pub const SHA256_WITH_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 11];
+pub const SHA384_WITH_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 12];
+pub const SHA512_WITH_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 13];
+pub const EC_PUBLIC_KEY: &[u64] = &[1, 2, 840, 10045, 2, 1];
+pub const ECDSA_WITH_SHA256: &[u64] = &[1, 2, 840, 10045, 4, 3, 2];
+pub const ECDSA_WITH_SHA384: &[u64] = &[1, 2, 840, 10045, 4, 3, 3];
+pub const PRIME256V1: &[u64] = &[1, 2, 840, 10045, 3, 1, 7];
+pub const SECP384R1: &[u64] = &[1, 3, 132, 0, 34];
+pub const SHA1: &[u64] = &[1, 3, 14, 3, 2, 26];
+pub const SHA256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+
+pub const COMMON_NAME: &[u64] = &[2, 5, 4, 3];
+pub const SERIAL_NUMBER: &[u64] = &[2, 5, 4, 5];
+pub const COUNTRY_NAME: &[u64] = &[2, 5, 4, 6];
+pub const LOCALITY_NAME: &[u64] = &[2, 5, 4, 7];
+pub const STATE_OR_PROVINCE_NAME: &[u64] = &[2, 5, 4, 8];
+pub const ORGANIZATION_NAME: &[u64] = &[2, 5, 4, 10];
+pub const ORGANIZATIONAL_UNIT_NAME: &[u64] = &[2, 5, 4, 11];
+pub const EMAIL_ADDRESS: &[u64] = &[1, 2, 840, 113549, 1, 9, 1];
+
+pub const SUBJECT_KEY_IDENTIFIER: &[u64] = &[2, 5, 29, 14];
+pub const KEY_USAGE: &[u64] = &[2, 5, 29, 15];
+pub const SUBJECT_ALT_NAME: &[u64] = &[2, 5, 29, 17];
+pub const BASIC_CONSTRAINTS: &[u64] = &[2, 5, 29, 19];
+pub const CRL_DISTRIBUTION_POINTS: &[u64] = &[2, 5, 29, 31];
+pub const CERTIFICATE_POLICIES: &[u64] = &[2, 5, 29, 32];
+pub const AUTHORITY_KEY_IDENTIFIER: &[u64] = &[2, 5, 29, 35];
+pub const EXT_KEY_USAGE: &[u64] = &[2, 5, 29, 37];
+pub const AUTHORITY_INFO_ACCESS: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 1, 1];
+
+#[cfg(feature = "oid-registry")]
+const REGISTRY: &[(&[u64], &str)] = &[
+    (RSA_ENCRYPTION, "rsaEncryption"),
+    (SHA1_WITH_RSA_ENCRYPTION, "sha1WithRSAEncryption"),
+    (SHA256_WITH_RSA_ENCRYPTION, "sha256WithRSAEncryption"),
+    (SHA384_WITH_RSA_ENCRYPTION, "sha384WithRSAEncryption"),
+    (SHA512_WITH_RSA_ENCRYPTION, "sha512WithRSAEncryption"),
+    (EC_PUBLIC_KEY, "ecPublicKey"),
+    (ECDSA_WITH_SHA256, "ecdsa-with-SHA256"),
+    (ECDSA_WITH_SHA384, "ecdsa-with-SHA384"),
+    (PRIME256V1, "prime256v1"),
+    (SECP384R1, "secp384r1"),
+    (SHA1, "sha1"),
+    (SHA256, "sha256"),
+    (COMMON_NAME, "commonName"),
+    (SERIAL_NUMBER, "serialNumber"),
+    (COUNTRY_NAME, "countryName"),
+    (LOCALITY_NAME, "localityName"),
+    (STATE_OR_PROVINCE_NAME, "stateOrProvinceName"),
+    (ORGANIZATION_NAME, "organizationName"),
+    (ORGANIZATIONAL_UNIT_NAME, "organizationalUnitName"),
+    (EMAIL_ADDRESS, "emailAddress"),
+    (SUBJECT_KEY_IDENTIFIER, "subjectKeyIdentifier"),
+    (KEY_USAGE, "keyUsage"),
+    (SUBJECT_ALT_NAME, "subjectAltName"),
+    (BASIC_CONSTRAINTS, "basicConstraints"),
+    (CRL_DISTRIBUTION_POINTS, "cRLDistributionPoints"),
+    (CERTIFICATE_POLICIES, "certificatePolicies"),
+    (AUTHORITY_KEY_IDENTIFIER, "authorityKeyIdentifier"),
+    (EXT_KEY_USAGE, "extKeyUsage"),
+    (AUTHORITY_INFO_ACCESS, "authorityInfoAccess"),
+];
+
+/// Object identifier
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::oid::{self, Oid};
+/// let oid: Oid = "1.2.840.113549.1.1.11".parse().unwrap();
+/// assert_eq!(oid.to_der(), [0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B]);
+/// assert_eq!(oid, oid::SHA256_WITH_RSA_ENCRYPTION);
+/// assert_eq!(oid.to_string(), "1.2.840.113549.1.1.11");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Oid {
+    arcs: Vec<u64>,
+}
+
+impl Oid {
+    /// Creates object identifier, first arc must be 0, 1 or 2 and second arc must be
+    /// less than 40 unless first arc is 2
+    pub fn new(arcs: &[u64]) -> Result<Oid> {
+        match *arcs {
+            [first, second, ..] if first < 2 && second < 40 => (),
+            [2, second, ..] if second <= u64::MAX - 80 => (),
+            _ => return Err(invalid()),
+        }
+
+        Ok(Oid {
+            arcs: arcs.to_vec(),
+        })
+    }
+
+    /// Returns arcs
+    pub fn arcs(&self) -> &[u64] {
+        &self.arcs
+    }
+
+    /// Decodes content octets of OBJECT IDENTIFIER
+    pub fn from_der(val: &[u8]) -> Result<Oid> {
+        if val.is_empty() || val[val.len() - 1] & 0x80 != 0 {
+            return Err(invalid());
+        }
+
+        let mut arcs = Vec::new();
+        let mut arc: u64 = 0;
+
+        for (i, &x) in val.iter().enumerate() {
+            // subidentifier must be encoded in the fewest possible octets
+            let starts = i == 0 || val[i - 1] & 0x80 == 0;
+            if (starts && x == 0x80) || arc.leading_zeros() < 7 {
+                return Err(invalid());
+            }
+            arc = arc << 7 | (x & 0x7F) as u64;
+
+            if x & 0x80 == 0 {
+                if arcs.is_empty() {
+                    let top = (arc / 40).min(2);
+                    arcs.push(top);
+                    arcs.push(arc - top * 40);
+                } else {
+                    arcs.push(arc);
+                }
+                arc = 0;
+            }
+        }
+
+        Ok(Oid { arcs })
+    }
+
+    /// Returns content octets of OBJECT IDENTIFIER
+    pub fn to_der(&self) -> Vec<u8> {
+        let first = self.arcs[0] * 40 + self.arcs[1];
+
+        let mut out = Vec::new();
+        for arc in core::iter::once(first).chain(self.arcs[2..].iter().cloned()) {
+            let groups = (64 - arc.leading_zeros() as usize).div_ceil(7).max(1);
+            for i in (0..groups).rev() {
+                let x = (arc >> (7 * i)) as u8 & 0x7F;
+                out.push(if i != 0 { x | 0x80 } else { x });
+            }
+        }
+        out
+    }
+
+    /// Parses OBJECT IDENTIFIER data object
+    pub fn from_tlv(tlv: &Tlv) -> Result<Oid> {
+        if tlv.tag() != OID_TAG {
+            return Err(TlvError::InvalidTagNumber);
+        }
+
+        match *tlv.val() {
+            Value::Val(ref v) => Oid::from_der(v),
+            Value::Nothing => Err(invalid()),
+            Value::TlvList(_) => Err(TlvError::ValExpected {
+                tag_number: OID_TAG,
+            }),
+            Value::Invalid { ref error, .. } => Err(error.clone()),
+        }
+    }
+
+    /// Returns OBJECT IDENTIFIER data object
+    pub fn to_tlv(&self) -> Tlv {
+        Tlv::new(OID_TAG, Value::Val(self.to_der())).expect("OID tag is primitive")
+    }
+
+    /// Returns well-known name of object identifier
+    #[cfg(feature = "oid-registry")]
+    pub fn name(&self) -> Option<&'static str> {
+        REGISTRY
+            .iter()
+            .find(|&&(arcs, _)| arcs == &self.arcs[..])
+            .map(|&(_, name)| name)
+    }
+}
+
+/// Parses dotted form, e.g. `2.5.4.3`
+impl FromStr for Oid {
+    type Err = TlvError;
+
+    fn from_str(s: &str) -> Result<Oid> {
+        let arcs = s
+            .split('.')
+            .map(|x| match x.bytes().all(|c| c.is_ascii_digit()) {
+                true => x.parse().map_err(|_| invalid()),
+                false => Err(invalid()),
+            })
+            .collect::<Result<Vec<u64>>>()?;
+
+        Oid::new(&arcs)
+    }
+}
+
+/// Formats in dotted form
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, arc) in self.arcs.iter().enumerate() {
+            if i != 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{}", arc)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq<[u64]> for Oid {
+    fn eq(&self, other: &[u64]) -> bool {
+        self.arcs == other
+    }
+}
+
+impl PartialEq<&[u64]> for Oid {
+    fn eq(&self, other: &&[u64]) -> bool {
+        self.arcs == *other
+    }
+}
+
+fn invalid() -> TlvError {
+    TlvError::InvalidValue {
+        tag_number: OID_TAG,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn der_test() {
+        let oid = Oid::new(&[2, 5, 4, 3]).unwrap();
+        assert_eq!(oid.to_der(), [0x55, 0x04, 0x03]);
+        assert_eq!(Oid::from_der(&[0x55, 0x04, 0x03]).unwrap(), oid);
+
+        let oid = Oid::new(&[2, 999, 3]).unwrap();
+        assert_eq!(oid.to_der(), [0x88, 0x37, 0x03]);
+        assert_eq!(Oid::from_der(&[0x88, 0x37, 0x03]).unwrap(), oid);
+
+        let oid = Oid::new(&[1, 2, u64::MAX]).unwrap();
+        assert_eq!(Oid::from_der(&oid.to_der()).unwrap(), oid);
+
+        assert!(Oid::new(&[1]).is_err());
+        assert!(Oid::new(&[1, 40]).is_err());
+        assert!(Oid::new(&[3, 1]).is_err());
+
+        // non-minimal subidentifier, truncated subidentifier and overflow
+        assert!(Oid::from_der(&[0x55, 0x80, 0x03]).is_err());
+        assert!(Oid::from_der(&[0x55, 0x84]).is_err());
+        assert!(Oid::from_der(&[]).is_err());
+        assert!(
+            Oid::from_der(&[0x55, 0x82, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn string_test() {
+        let oid: Oid = "1.3.6.1.5.5.7.1.1".parse().unwrap();
+        assert_eq!(oid, AUTHORITY_INFO_ACCESS);
+        assert_ne!(oid, SHA1);
+        assert_eq!(oid.to_string(), "1.3.6.1.5.5.7.1.1");
+
+        for s in ["", "1", "1..2", "1.2.", "1.+2", "3.1", "1.2.x"] {
+            assert!(s.parse::<Oid>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn tlv_test() {
+        let tlv = Tlv::from_vec(&[0x06, 0x03, 0x55, 0x1D, 0x13]).unwrap();
+        let oid = Oid::from_tlv(&tlv).unwrap();
+        assert_eq!(oid, BASIC_CONSTRAINTS);
+        assert_eq!(oid.to_tlv(), tlv);
+
+        assert!(Oid::from_tlv(&Tlv::from_vec(&[0x04, 0x01, 0x55]).unwrap()).is_err());
+    }
+
+    #[cfg(feature = "oid-registry")]
+    #[test]
+    fn registry_test() {
+        assert_eq!(
+            Oid::new(EMAIL_ADDRESS).unwrap().name(),
+            Some("emailAddress")
+        );
+        assert_eq!(Oid::new(&[1, 2, 3]).unwrap().name(), None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Borrowed view of BER-TLV data which parses children on demand.
+//!
+//! Unlike [`Tlv`], nothing is copied or allocated while walking the tree: a lookup
+//! like `6F / A5 / BF0C / 61` decodes only headers of data objects on the path and
+//! of their preceding siblings.
+
+use crate::tlv::{Tag, Tlv};
+use crate::Result;
+
+/// Data object referencing encoded input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawTlv<'a> {
+    tag: Tag,
+    /// Encoding of data object including header
+    bytes: &'a [u8],
+    header_len: usize,
+}
+
+impl<'a> RawTlv<'a> {
+    /// Reads header of the first data object of `slice`
+    ///
+    /// Leading `00` padding is skipped and trailing bytes are ignored. Value is
+    /// not inspected until [`RawTlv::children`] is iterated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::raw::RawTlv;
+    /// let tlv = RawTlv::from_slice(&[0x6F, 0x04, 0x84, 0x02, 0xA0, 0x00]).unwrap();
+    /// assert_eq!(tlv.tag(), 0x6F);
+    /// assert_eq!(tlv.value_bytes(), [0x84, 0x02, 0xA0, 0x00]);
+    /// ```
+    pub fn from_slice(slice: &'a [u8]) -> Result<RawTlv<'a>> {
+        RawTlv::read(slice).map(|(tlv, _)| tlv)
+    }
+
+    /// Returns data object and number of bytes it occupies in `slice`, including padding
+    fn read(slice: &'a [u8]) -> Result<(RawTlv<'a>, usize)> {
+        let padding = slice.iter().take_while(|&&x| x == 0).count();
+        let data = &slice[padding..];
+
+        let mut iter = data.iter();
+        let tag = Tlv::read_tag(&mut iter)?;
+        let len = Tlv::read_len(&mut iter)?;
+        let header_len = data.len() - iter.len();
+
+        let tlv = RawTlv {
+            tag,
+            bytes: &data[..header_len + len],
+            header_len,
+        };
+        Ok((tlv, padding + header_len + len))
+    }
+
+    /// Returns tag number
+    pub fn tag(&self) -> Tag {
+        self.tag
+    }
+
+    /// Returns value without tag and length fields
+    pub fn value_bytes(&self) -> &'a [u8] {
+        &self.bytes[self.header_len..]
+    }
+
+    /// Returns encoding of data object including tag and length fields
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns true if tag number defines primitive TLV
+    pub fn is_primitive(&self) -> bool {
+        Tlv::is_primitive_tag(self.tag)
+    }
+
+    /// Returns iterator over children of constructed data object
+    ///
+    /// Each item is decoded when requested; iteration stops after the first error.
+    /// Primitive data object has no children.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::raw::RawTlv;
+    /// let tlv = RawTlv::from_slice(&[0x70, 0x07, 0x5A, 0x01, 0x47, 0x00, 0x9F, 0x08, 0x00]).unwrap();
+    /// let tags: Vec<_> = tlv.children().map(|x| x.unwrap().tag()).collect();
+    /// assert_eq!(tags, [0x5A, 0x9F08]);
+    /// ```
+    pub fn children(&self) -> Children<'a> {
+        Children {
+            rest: match self.is_primitive() {
+                true => &[],
+                false => self.value_bytes(),
+            },
+        }
+    }
+
+    /// Returns data object by tag path, see [`Tlv::find_val`] for its syntax
+    ///
+    /// Only headers of preceding siblings are decoded, so malformed values of
+    /// unrelated data objects don't prevent the lookup.
+    pub fn find(&self, path: &str) -> Option<RawTlv<'a>> {
+        let path = Tlv::get_path(path).ok()?;
+        let (&first, rest) = path.split_first()?;
+        if first != self.tag {
+            return None;
+        }
+
+        let mut tlv = *self;
+        for &tag in rest {
+            tlv = tlv
+                .children()
+                .map_while(|x| x.ok())
+                .find(|x| x.tag == tag)?;
+        }
+
+        Some(tlv)
+    }
+
+    /// Returns value of data object by tag path
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::raw::RawTlv;
+    /// let input = [0x6F, 0x09, 0xA5, 0x07, 0xBF, 0x0C, 0x04, 0xDF, 0x7F, 0x01, 0x55];
+    /// let tlv = RawTlv::from_slice(&input).unwrap();
+    /// assert_eq!(tlv.find_val("6F / A5 / BF0C / DF7F"), Some(&[0x55][..]));
+    /// assert_eq!(tlv.find_val("6F / 84"), None);
+    /// ```
+    pub fn find_val(&self, path: &str) -> Option<&'a [u8]> {
+        self.find(path).map(|x| x.value_bytes())
+    }
+
+    /// Parses the whole data object into [`Tlv`]
+    pub fn to_tlv(&self) -> Result<Tlv> {
+        Tlv::from_vec(self.bytes)
+    }
+}
+
+/// Iterator over children of [`RawTlv`]
+#[derive(Debug, Clone)]
+pub struct Children<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = Result<RawTlv<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // skip '00' padding, including trailing one
+        let padding = self.rest.iter().take_while(|&&x| x == 0).count();
+        self.rest = &self.rest[padding..];
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        match RawTlv::read(self.rest) {
+            Ok((tlv, size)) => {
+                self.rest = &self.rest[size..];
+                Some(Ok(tlv))
+            }
+            Err(error) => {
+                self.rest = &[];
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl core::iter::FusedIterator for Children<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tlv::Value;
+    use crate::TlvError;
+    use alloc::vec::Vec;
+
+    const FCI: [u8; 34] = [
+        0x6F, 0x20, 0x84, 0x07, 0xA0, 0x00, 0x00, 0x00, 0x04, 0x10, 0x10, 0xA5, 0x15, 0x50, 0x0A,
+        0x4D, 0x61, 0x73, 0x74, 0x65, 0x72, 0x43, 0x61, 0x72, 0x64, 0xBF, 0x0C, 0x06, 0x61, 0x04,
+        0x9F, 0x02, 0x01, 0x01,
+    ];
+
+    #[test]
+    fn find_test() {
+        let raw = RawTlv::from_slice(&FCI).unwrap();
+        let tlv = Tlv::from_vec(&FCI).unwrap();
+
+        for path in [
+            "6F",
+            "6F / 84",
+            "6F / A5 / 50",
+            "6F / A5 / BF0C",
+            "6F / A5 / BF0C / 61 / 9F02",
+            "6F / A5 / 88",
+            "6F / 50",
+            "84",
+            "",
+            "6F / XY",
+        ] {
+            let expected = match tlv.find_val(path) {
+                Some(Value::Val(v)) => Some(v.clone()),
+                Some(Value::TlvList(list)) => Some(list.iter().flat_map(|x| x.to_vec()).collect()),
+                _ => None,
+            };
+            assert_eq!(raw.find_val(path).map(<[u8]>::to_vec), expected, "{path}");
+        }
+
+        let fci = raw.find("6F / A5 / BF0C / 61").unwrap();
+        assert_eq!(fci.as_bytes(), [0x61, 0x04, 0x9F, 0x02, 0x01, 0x01]);
+        assert_eq!(fci.to_tlv().unwrap().tag(), 0x61);
+        assert!(!fci.is_primitive());
+    }
+
+    #[test]
+    fn children_test() {
+        let raw =
+            RawTlv::from_slice(&[0x00, 0x70, 0x06, 0x00, 0x5A, 0x01, 0x47, 0x00, 0x00]).unwrap();
+        let children: Vec<_> = raw.children().collect::<Result<_>>().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].value_bytes(), [0x47]);
+        assert_eq!(children[0].children().count(), 0);
+
+        // sibling before the match is skipped by its header only
+        let input = [0x70, 0x08, 0x71, 0x03, 0x00, 0x9F, 0x82, 0x5A, 0x01, 0x47];
+        let raw = RawTlv::from_slice(&input).unwrap();
+        assert_eq!(raw.find_val("70 / 5A"), Some(&[0x47][..]));
+        assert!(Tlv::from_vec(&input).is_err());
+
+        // the rest is not reached after malformed child
+        let raw = RawTlv::from_slice(&[0x70, 0x05, 0x5A, 0x05, 0x47, 0x84, 0x00]).unwrap();
+        let mut children = raw.children();
+        assert!(matches!(
+            children.next(),
+            Some(Err(TlvError::TooShortBody { .. }))
+        ));
+        assert!(children.next().is_none());
+        assert_eq!(raw.find("70 / 84"), None);
+
+        assert_eq!(
+            RawTlv::from_slice(&[0x70, 0x05, 0x5A]),
+            Err(TlvError::TooShortBody {
+                expected: 5,
+                found: 1
+            })
+        );
+        assert_eq!(RawTlv::from_slice(&[0x00; 2]), Err(TlvError::TruncatedTlv));
+    }
+}
@@ -0,0 +1,500 @@
+//! Declarative validation of TLV trees.
+//!
+//! [`Schema`] describes which tags may appear in which template, how many times,
+//! and what length and format their values must have. Built-in schemas cover
+//! card responses defined in EMV 4.3 Book 1 and Book 3.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::tlv::{Tag, Tlv, Value};
+use crate::TlvError;
+
+/// Format of primitive value (EMV 4.3 Book 3, section 4.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueFormat {
+    /// Any bytes (`b`)
+    Binary,
+    /// BCD digits (`n`)
+    Numeric,
+    /// BCD digits padded with trailing `F` (`cn`)
+    CompressedNumeric,
+    /// Letters and digits (`an`)
+    Alphanumeric,
+    /// Printable ASCII characters (`ans`)
+    AlphanumericSpecial,
+}
+
+impl ValueFormat {
+    /// Returns true if value is coded according to the format
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::schema::ValueFormat;
+    /// assert!(ValueFormat::Numeric.is_valid(&[0x24, 0x12, 0x31]));
+    /// assert!(ValueFormat::CompressedNumeric.is_valid(&[0x47, 0x61, 0xFF]));
+    /// assert!(!ValueFormat::CompressedNumeric.is_valid(&[0x4F, 0x61]));
+    /// ```
+    pub fn is_valid(&self, val: &[u8]) -> bool {
+        let mut nibbles = val.iter().flat_map(|x| [x >> 4, x & 0x0F]);
+
+        match *self {
+            ValueFormat::Binary => true,
+            ValueFormat::Numeric => nibbles.all(|x| x <= 9),
+            ValueFormat::CompressedNumeric => match nibbles.by_ref().find(|&x| x > 9) {
+                Some(0x0F) | None => nibbles.all(|x| x == 0x0F),
+                Some(_) => false,
+            },
+            ValueFormat::Alphanumeric => val.iter().all(|x| x.is_ascii_alphanumeric()),
+            ValueFormat::AlphanumericSpecial => val.iter().all(|&x| (0x20..=0x7E).contains(&x)),
+        }
+    }
+}
+
+/// Constraints of data object in template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub tag: Tag,
+    pub required: bool,
+    /// Maximum number of occurrences in parent template
+    pub max_occurs: usize,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub format: ValueFormat,
+    /// Allowed children of constructed data object
+    pub children: Vec<Rule>,
+    /// Allow children not described by the schema
+    pub other_children: bool,
+}
+
+impl Rule {
+    /// Returns optional non-repeatable primitive data object
+    pub fn primitive(tag: Tag, min_len: usize, max_len: usize, format: ValueFormat) -> Rule {
+        Rule {
+            tag,
+            required: false,
+            max_occurs: 1,
+            min_len,
+            max_len,
+            format,
+            children: Vec::new(),
+            other_children: false,
+        }
+    }
+
+    /// Returns optional non-repeatable template with given children
+    pub fn template(tag: Tag, children: Vec<Rule>) -> Rule {
+        Rule {
+            children,
+            ..Rule::primitive(tag, 0, usize::MAX, ValueFormat::Binary)
+        }
+    }
+
+    /// Makes data object mandatory
+    pub fn required(mut self) -> Rule {
+        self.required = true;
+        self
+    }
+
+    /// Sets maximum number of occurrences
+    pub fn max_occurs(mut self, max_occurs: usize) -> Rule {
+        self.max_occurs = max_occurs;
+        self
+    }
+
+    /// Allows proprietary children which are not described elsewhere in schema
+    pub fn allow_other(mut self) -> Rule {
+        self.other_children = true;
+        self
+    }
+
+    fn defines(&self, tag: Tag) -> bool {
+        self.tag == tag || self.children.iter().any(|x| x.defines(tag))
+    }
+}
+
+/// Kind of schema violation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Tag is not allowed in this place
+    UnexpectedTag,
+    /// Mandatory tag is missing
+    MissingTag,
+    /// Non-repeatable tag is repeated or repeated too many times
+    TooManyOccurrences { found: usize, max: usize },
+    /// Length of value is outside of allowed range
+    InvalidLength {
+        found: usize,
+        min: usize,
+        max: usize,
+    },
+    /// Value is not coded according to format
+    InvalidFormat(ValueFormat),
+    /// Data object could not be parsed by lenient parser
+    Malformed(TlvError),
+}
+
+/// Problem found by schema validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Path from root to offending tag
+    pub path: Vec<Tag>,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, tag) in self.path.iter().enumerate() {
+            if i != 0 {
+                write!(f, " / ")?;
+            }
+            write!(f, "{:02X}", tag)?;
+        }
+
+        match self.kind {
+            ViolationKind::UnexpectedTag => write!(f, ": unexpected tag"),
+            ViolationKind::MissingTag => write!(f, ": missing mandatory tag"),
+            ViolationKind::TooManyOccurrences { found, max } => {
+                write!(f, ": {} occurrences, at most {} allowed", found, max)
+            }
+            ViolationKind::InvalidLength { found, min, max } => {
+                write!(f, ": invalid length {}, expected {}..={}", found, min, max)
+            }
+            ViolationKind::InvalidFormat(format) => write!(f, ": value is not {:?}", format),
+            ViolationKind::Malformed(ref error) => write!(f, ": malformed data object: {}", error),
+        }
+    }
+}
+
+/// Set of alternative root templates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    roots: Vec<Rule>,
+}
+
+impl Schema {
+    /// Creates schema accepting any of given roots
+    pub fn new(roots: Vec<Rule>) -> Schema {
+        Schema { roots }
+    }
+
+    /// Returns all violations found in TLV tree
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::tlv::Tlv;
+    /// # use tlv_parser::schema::*;
+    /// // Application Label is too long
+    /// let fci = Tlv::from_vec(&[
+    ///     0x6F, 0x1D, 0x84, 0x05, 0xA0, 0x00, 0x00, 0x00, 0x03, 0xA5, 0x14, 0x50, 0x12, 0x41,
+    ///     0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41,
+    ///     0x41, 0x41, 0x41,
+    /// ]).unwrap();
+    ///
+    /// let violations = Schema::fci().validate(&fci);
+    /// assert_eq!(violations.len(), 1);
+    /// assert_eq!(
+    ///     violations[0].to_string(),
+    ///     "6F / A5 / 50: invalid length 18, expected 1..=16"
+    /// );
+    /// ```
+    pub fn validate(&self, tlv: &Tlv) -> Vec<Violation> {
+        let mut out = Vec::new();
+        let mut path = vec![tlv.tag()];
+
+        match self.roots.iter().find(|x| x.tag == tlv.tag()) {
+            Some(rule) => self.check(rule, tlv, &mut path, &mut out),
+            None => out.push(Violation {
+                path,
+                kind: ViolationKind::UnexpectedTag,
+            }),
+        }
+
+        out
+    }
+
+    /// Returns schema of SELECT response (File Control Information)
+    pub fn fci() -> Schema {
+        use ValueFormat::*;
+
+        Schema::new(vec![Rule::template(
+            0x6F,
+            vec![
+                Rule::primitive(0x84, 5, 16, Binary).required(),
+                Rule::template(
+                    0xA5,
+                    vec![
+                        Rule::primitive(0x88, 1, 1, Binary),
+                        Rule::primitive(0x50, 1, 16, AlphanumericSpecial),
+                        Rule::primitive(0x87, 1, 1, Binary),
+                        Rule::primitive(0x9F38, 0, 252, Binary),
+                        Rule::primitive(0x5F2D, 2, 8, Alphanumeric),
+                        Rule::primitive(0x9F11, 1, 1, Numeric),
+                        Rule::primitive(0x9F12, 1, 16, AlphanumericSpecial),
+                        Rule::template(0xBF0C, Vec::new()).allow_other(),
+                    ],
+                )
+                .required()
+                .allow_other(),
+            ],
+        )])
+    }
+
+    /// Returns schema of GET PROCESSING OPTIONS response (format 1 or 2)
+    pub fn gpo_response() -> Schema {
+        use ValueFormat::*;
+
+        Schema::new(vec![
+            Rule::primitive(0x80, 2, 254, Binary),
+            Rule::template(
+                0x77,
+                vec![
+                    Rule::primitive(0x82, 2, 2, Binary).required(),
+                    Rule::primitive(0x94, 0, 252, Binary),
+                    Rule::primitive(0x9F36, 2, 2, Binary),
+                    Rule::primitive(0x9F10, 0, 32, Binary),
+                    Rule::primitive(0x9F26, 8, 8, Binary),
+                    Rule::primitive(0x9F27, 1, 1, Binary),
+                    Rule::primitive(0x9F4B, 0, 255, Binary),
+                    Rule::primitive(0x57, 0, 19, Binary),
+                    Rule::primitive(0x5F34, 1, 1, Numeric),
+                ],
+            )
+            .allow_other(),
+        ])
+    }
+
+    /// Returns schema of READ RECORD response (record template `70`)
+    pub fn read_record() -> Schema {
+        use ValueFormat::*;
+
+        Schema::new(vec![Rule::template(
+            0x70,
+            vec![
+                Rule::primitive(0x57, 0, 19, Binary),
+                Rule::primitive(0x5A, 0, 10, CompressedNumeric),
+                Rule::primitive(0x5F20, 2, 26, AlphanumericSpecial),
+                Rule::primitive(0x5F24, 3, 3, Numeric),
+                Rule::primitive(0x5F25, 3, 3, Numeric),
+                Rule::primitive(0x5F28, 2, 2, Numeric),
+                Rule::primitive(0x5F30, 2, 2, Numeric),
+                Rule::primitive(0x5F34, 1, 1, Numeric),
+                Rule::primitive(0x8C, 0, 252, Binary),
+                Rule::primitive(0x8D, 0, 252, Binary),
+                Rule::primitive(0x8E, 10, 252, Binary),
+                Rule::primitive(0x8F, 1, 1, Binary),
+                Rule::primitive(0x90, 0, 255, Binary),
+                Rule::primitive(0x92, 0, 255, Binary),
+                Rule::primitive(0x93, 0, 255, Binary),
+                Rule::primitive(0x9F07, 2, 2, Binary),
+                Rule::primitive(0x9F08, 2, 2, Binary),
+                Rule::primitive(0x9F0D, 5, 5, Binary),
+                Rule::primitive(0x9F0E, 5, 5, Binary),
+                Rule::primitive(0x9F0F, 5, 5, Binary),
+                Rule::primitive(0x9F1F, 0, 255, AlphanumericSpecial),
+                Rule::primitive(0x9F32, 1, 3, Binary),
+                Rule::primitive(0x9F46, 0, 255, Binary),
+                Rule::primitive(0x9F47, 1, 3, Binary),
+                Rule::primitive(0x9F48, 0, 255, Binary),
+                Rule::primitive(0x9F49, 0, 252, Binary),
+                Rule::primitive(0x9F4A, 0, 255, Binary),
+            ],
+        )
+        .allow_other()])
+    }
+
+    /// Returns schema of GENERATE AC response (format 1 or 2)
+    pub fn gen_ac_response() -> Schema {
+        use ValueFormat::*;
+
+        Schema::new(vec![
+            Rule::primitive(0x80, 11, 43, Binary),
+            Rule::template(
+                0x77,
+                vec![
+                    Rule::primitive(0x9F27, 1, 1, Binary).required(),
+                    Rule::primitive(0x9F36, 2, 2, Binary).required(),
+                    // absent if CDA signature is returned
+                    Rule::primitive(0x9F26, 8, 8, Binary),
+                    Rule::primitive(0x9F10, 0, 32, Binary),
+                    Rule::primitive(0x9F4B, 0, 255, Binary),
+                ],
+            )
+            .allow_other(),
+        ])
+    }
+
+    fn defines(&self, tag: Tag) -> bool {
+        self.roots.iter().any(|x| x.defines(tag))
+    }
+
+    fn check(&self, rule: &Rule, tlv: &Tlv, path: &mut Vec<Tag>, out: &mut Vec<Violation>) {
+        let list = match *tlv.val() {
+            Value::TlvList(ref list) => list,
+            Value::Val(ref v) => return check_value(rule, v, path, out),
+            Value::Nothing => return check_value(rule, &[], path, out),
+            // reported with parent
+            Value::Invalid { .. } => return,
+        };
+
+        for child in list {
+            path.push(child.tag());
+            match (
+                child.val(),
+                rule.children.iter().find(|x| x.tag == child.tag()),
+            ) {
+                (Value::Invalid { error, .. }, _) => out.push(Violation {
+                    path: path.clone(),
+                    kind: ViolationKind::Malformed(error.clone()),
+                }),
+                (_, Some(child_rule)) => self.check(child_rule, child, path, out),
+                (_, None) if rule.other_children && !self.defines(child.tag()) => (),
+                (_, None) => out.push(Violation {
+                    path: path.clone(),
+                    kind: ViolationKind::UnexpectedTag,
+                }),
+            }
+            path.pop();
+        }
+
+        for child_rule in &rule.children {
+            let found = list.iter().filter(|x| x.tag() == child_rule.tag).count();
+
+            let kind = if found == 0 && child_rule.required {
+                ViolationKind::MissingTag
+            } else if found > child_rule.max_occurs {
+                ViolationKind::TooManyOccurrences {
+                    found,
+                    max: child_rule.max_occurs,
+                }
+            } else {
+                continue;
+            };
+
+            path.push(child_rule.tag);
+            out.push(Violation {
+                path: path.clone(),
+                kind,
+            });
+            path.pop();
+        }
+    }
+}
+
+fn check_value(rule: &Rule, val: &[u8], path: &[Tag], out: &mut Vec<Violation>) {
+    if val.len() < rule.min_len || val.len() > rule.max_len {
+        out.push(Violation {
+            path: path.to_vec(),
+            kind: ViolationKind::InvalidLength {
+                found: val.len(),
+                min: rule.min_len,
+                max: rule.max_len,
+            },
+        });
+    }
+
+    if !rule.format.is_valid(val) {
+        out.push(Violation {
+            path: path.to_vec(),
+            kind: ViolationKind::InvalidFormat(rule.format),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fci_test() {
+        // SELECT response from Amex CDA trace
+        let fci = Tlv::from_vec(&[
+            0x6F, 0x2C, 0x84, 0x08, 0xA0, 0x00, 0x00, 0x00, 0x25, 0x01, 0x04, 0x03, 0xA5, 0x20,
+            0x50, 0x10, 0x41, 0x6D, 0x65, 0x72, 0x69, 0x63, 0x61, 0x6E, 0x20, 0x45, 0x78, 0x70,
+            0x72, 0x65, 0x73, 0x73, 0x9F, 0x38, 0x03, 0x9F, 0x35, 0x01, 0x87, 0x01, 0x01, 0x5F,
+            0x2D, 0x02, 0x65, 0x6E,
+        ])
+        .unwrap();
+        assert!(Schema::fci().validate(&fci).is_empty());
+
+        // DF Name is missing, Application Priority Indicator is repeated
+        // and Application Label is placed outside of A5
+        let fci = Tlv::from_vec(&[
+            0x6F, 0x0C, 0xA5, 0x06, 0x87, 0x01, 0x01, 0x87, 0x01, 0x02, 0x50, 0x02, 0x41, 0x42,
+        ])
+        .unwrap();
+        assert_eq!(
+            Schema::fci().validate(&fci),
+            [
+                Violation {
+                    path: vec![0x6F, 0xA5, 0x87],
+                    kind: ViolationKind::TooManyOccurrences { found: 2, max: 1 }
+                },
+                Violation {
+                    path: vec![0x6F, 0x50],
+                    kind: ViolationKind::UnexpectedTag
+                },
+                Violation {
+                    path: vec![0x6F, 0x84],
+                    kind: ViolationKind::MissingTag
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn record_test() {
+        let record = Tlv::from_vec(&[
+            0x70, 0x12, 0x5A, 0x03, 0x47, 0x61, 0xFF, 0x5F, 0x24, 0x03, 0x24, 0x1A, 0x31, 0x9F,
+            0x55, 0x01, 0x00, 0x9F, 0x36, 0x00,
+        ])
+        .unwrap();
+
+        let violations = Schema::read_record().validate(&record);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, [0x70, 0x5F24]);
+        assert_eq!(
+            violations[0].kind,
+            ViolationKind::InvalidFormat(ValueFormat::Numeric)
+        );
+
+        let gpo = Tlv::from_vec(&[0x70, 0x03, 0x82, 0x01, 0x00]).unwrap();
+        assert_eq!(
+            Schema::gpo_response().validate(&gpo),
+            [Violation {
+                path: vec![0x70],
+                kind: ViolationKind::UnexpectedTag
+            }]
+        );
+    }
+
+    #[test]
+    fn gen_ac_test() {
+        let resp = Tlv::from_vec(&[
+            0x77, 0x17, 0x9F, 0x27, 0x01, 0x80, 0x9F, 0x26, 0x08, 0x01, 0x02, 0x03, 0x04, 0x05,
+            0x06, 0x07, 0x08, 0x9F, 0x36, 0x01, 0x01, 0x82, 0x02, 0x19, 0x80,
+        ])
+        .unwrap();
+
+        assert_eq!(
+            Schema::gen_ac_response().validate(&resp),
+            [Violation {
+                path: vec![0x77, 0x9F36],
+                kind: ViolationKind::InvalidLength {
+                    found: 1,
+                    min: 2,
+                    max: 2
+                }
+            }]
+        );
+
+        let resp = Tlv::from_vec(&[
+            0x80, 0x0B, 0x80, 0x00, 0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+        ])
+        .unwrap();
+        assert!(Schema::gen_ac_response().validate(&resp).is_empty());
+    }
+}
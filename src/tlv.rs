@@ -1,18 +1,33 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::{vec, vec::Vec};
 use core::fmt::{self, Debug};
+use core::mem::size_of;
 
 use super::{Result, TlvError};
 
 pub type Tag = usize;
+
+/// Length of value in bytes
+pub type Length = usize;
 type Tags = Vec<Tag>;
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Value {
     TlvList(Vec<Tlv>),
     Val(Vec<u8>),
     Nothing,
+    /// Unparseable remainder of constructed value kept by lenient parser,
+    /// `bytes` include header of malformed data object
+    Invalid {
+        bytes: Vec<u8>,
+        error: TlvError,
+    },
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Tlv {
     tag: Tag,
     val: Value,
@@ -58,10 +73,10 @@ impl Tlv {
             Value::Val(val) => {
                 if !is_tlv_primitive {
                     let mut list = Vec::new();
-                    let mut idx = 0;
-                    while idx < val.len() {
-                        let tlv = Tlv::from_vec(&val[idx..])?;
-                        idx += tlv.len();
+                    let mut rest = &val[..];
+                    while !rest.is_empty() {
+                        let (tlv, next) = Tlv::from_vec_partial(rest)?;
+                        rest = next;
                         list.push(tlv);
                     }
                     Value::TlvList(list)
@@ -69,7 +84,8 @@ impl Tlv {
                     Value::Val(val)
                 }
             }
-            _ => Value::Nothing,
+            Value::Invalid { bytes, error } => Value::Invalid { bytes, error },
+            Value::Nothing => Value::Nothing,
         };
 
         Ok(tlv)
@@ -90,18 +106,7 @@ impl Tlv {
     /// assert_eq!(tag_len, 1);
     /// ```
     pub fn tag_len(&self) -> usize {
-        let mut tag = self.tag;
-        let mut len = 0;
-        while tag != 0 {
-            len += 1;
-            tag >>= 8;
-        }
-
-        if len == 0 {
-            len + 1
-        } else {
-            len
-        }
+        encoded_tag_len(self.tag)
     }
 
     /// Returns size of TLV-string in bytes
@@ -114,7 +119,13 @@ impl Tlv {
     /// assert_eq!(tlv_len, 4);
     /// ```
     pub fn len(&self) -> usize {
-        self.tag_len() + self.val.encode_len().len() + self.val.len()
+        match self.val {
+            Value::Invalid { ref bytes, .. } => bytes.len(),
+            _ => {
+                let len = self.val.len();
+                header_len(self.tag, len) + len
+            }
+        }
     }
 
     /// Returns true if Value of TLV is empty
@@ -140,30 +151,59 @@ impl Tlv {
     /// assert_eq!(tlv.to_vec(), vec![0x21, 0x08, 0x01, 0x02, 0xA1, 0xA2, 0x02, 0x02, 0xB1, 0xB2]);
     /// ```
     pub fn to_vec(&self) -> Vec<u8> {
-        let mut out: Vec<u8> = (self.tag as u64)
-            .to_be_bytes()
-            .iter()
-            .skip_while(|&&x| x == 0)
-            .cloned()
-            .collect();
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
 
-        out.append(&mut self.val.encode_len());
+    /// Appends encoding of TLV to `out`
+    ///
+    /// Lengths of values are computed in a single bottom-up pass beforehand, so
+    /// encoding of deep trees takes linear time.
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        let mut lens = Vec::new();
+        let total = self.collect_lens(&mut lens);
+        out.reserve(total);
+        self.write(&mut lens.into_iter(), out);
+    }
 
-        match self.val {
+    /// Pushes lengths of values in pre-order and returns size of TLV-string
+    fn collect_lens(&self, lens: &mut Vec<Length>) -> usize {
+        let len = match self.val {
+            Value::Invalid { ref bytes, .. } => return bytes.len(),
             Value::TlvList(ref list) => {
-                for x in list.iter() {
-                    out.append(&mut x.to_vec());
-                }
+                let i = lens.len();
+                lens.push(0);
+                let len = list.iter().map(|x| x.collect_lens(lens)).sum();
+                lens[i] = len;
+                len
             }
-            Value::Val(ref v) => out.extend_from_slice(v),
-            Value::Nothing => (),
+            Value::Val(ref v) => v.len(),
+            Value::Nothing => 0,
         };
 
-        out
+        header_len(self.tag, len) + len
+    }
+
+    /// Writes TLV-string using lengths collected by [`Tlv::collect_lens`]
+    fn write(&self, lens: &mut dyn Iterator<Item = Length>, out: &mut Vec<u8>) {
+        let len = match self.val {
+            Value::Invalid { ref bytes, .. } => return out.extend_from_slice(bytes),
+            Value::TlvList(_) => lens.next().unwrap_or_default(),
+            Value::Val(ref v) => v.len(),
+            Value::Nothing => 0,
+        };
+
+        out.extend_from_slice(&encode_header(self.tag, len));
+        match self.val {
+            Value::TlvList(ref list) => list.iter().for_each(|x| x.write(lens, out)),
+            Value::Val(ref v) => out.extend_from_slice(v),
+            Value::Nothing | Value::Invalid { .. } => (),
+        }
     }
 
     /// Parses string like "6F / A5" into Tags
-    fn get_path(path: &str) -> Result<Tags> {
+    pub(crate) fn get_path(path: &str) -> Result<Tags> {
         let tags: Result<Vec<_>> = path
             .chars()
             .filter(|&x| x.is_digit(16) || x == '/')
@@ -233,57 +273,60 @@ impl Tlv {
     }
 
     /// Reads out tag number
-    fn read_tag(iter: &mut dyn ExactSizeIterator<Item = &u8>) -> Result<Tag> {
-        let mut tag: usize;
-
+    pub(crate) fn read_tag(iter: &mut dyn ExactSizeIterator<Item = &u8>) -> Result<Tag> {
         // Per EMV 4.3 Book 3 Annex B1 (Coding of the Tag Field of BER-TLV Data Objects):
         // > Before, between, or after TLV-coded data objects, '00' bytes without any meaning
         // > may occur (for example, due to erased or modified TLV-coded data objects).
-        let first: u8 = iter
-            .skip_while(|&&next| next == 0)
-            .next()
-            .cloned()
-            .ok_or_else(|| TlvError::TruncatedTlv)?;
-        tag = first as usize;
+        let first: u8 = loop {
+            match iter.next() {
+                Some(0) => continue,
+                Some(&next) => break next,
+                None => return Err(TlvError::TruncatedTlv),
+            }
+        };
 
+        // the first octet and subsequent ones up to the last octet of long form
+        let mut buf = [first; size_of::<Tag>() + 1];
+        let mut size = 1;
         if first & 0x1F == 0x1F {
-            // long form - find the end
-            for x in &mut *iter {
-                tag = tag
-                    .checked_shl(8)
-                    .ok_or_else(|| TlvError::InvalidTagNumber)?;
-
-                tag |= *x as usize;
-
-                if *x & 0x80 == 0 {
-                    break;
+            while size < buf.len() {
+                match iter.next() {
+                    Some(&x) => {
+                        buf[size] = x;
+                        size += 1;
+                        if x & 0x80 == 0 {
+                            break;
+                        }
+                    }
+                    None => break,
                 }
             }
         }
 
-        if tag == 0 {
-            return Err(TlvError::InvalidTagNumber);
-        }
-
-        Ok(tag)
+        decode_tag(&buf[..size]).map(|(tag, _)| tag)
     }
 
     /// Reads out TLV value's length
-    fn read_len(iter: &mut dyn ExactSizeIterator<Item = &u8>) -> Result<usize> {
-        let mut len: usize;
-        len = *iter.next().ok_or_else(|| TlvError::TruncatedTlv)? as usize;
-
-        if len & 0x80 != 0 {
-            let octet_num = len & 0x7F;
-
-            len = 0;
-            for x in iter.take(octet_num) {
-                len = len.checked_shl(8).ok_or_else(|| TlvError::InvalidLength)?;
-
-                len |= *x as usize;
+    pub(crate) fn read_len(iter: &mut dyn ExactSizeIterator<Item = &u8>) -> Result<Length> {
+        // the first octet and at most 127 subsequent ones
+        let mut buf = [0u8; 128];
+        let mut size = 0;
+        if let Some(&first) = iter.next() {
+            let octets = match first & 0x80 {
+                0 => 0,
+                _ => (first & 0x7F) as usize,
+            };
+
+            buf[0] = first;
+            size = 1;
+            for (dst, &src) in buf[1..=octets].iter_mut().zip(&mut *iter) {
+                *dst = src;
+                size += 1;
             }
         }
 
+        let (len, _) = decode(&buf[..size], false)?;
+
         let remain = iter.len();
         if remain < len {
             Err(TlvError::TooShortBody {
@@ -301,10 +344,27 @@ impl Tlv {
         self.tag & mask != mask
     }
 
+    /// Returns true if tag number defines primitive TLV
+    pub(crate) fn is_primitive_tag(tag: Tag) -> bool {
+        Tlv {
+            tag,
+            val: Value::Nothing,
+        }
+        .is_primitive()
+    }
+
     /// Initializes Tlv object iterator of Vec<u8>
-    fn from_iter(iter: &mut dyn ExactSizeIterator<Item = &u8>) -> Result<Tlv> {
-        let tag = Tlv::read_tag(iter)?;
-        let len = Tlv::read_len(iter)?;
+    ///
+    /// `offset` is position of iterator within input of parser.
+    fn from_iter(
+        iter: &mut dyn ExactSizeIterator<Item = &u8>,
+        offset: usize,
+        parser: &mut Parser,
+    ) -> Result<Tlv> {
+        let remain = iter.len();
+        let tag = Tlv::read_tag(iter).map_err(|e| parser.context(None, offset, e))?;
+        let len = Tlv::read_len(iter).map_err(|e| parser.context(Some(tag), offset, e))?;
+        let val_offset = offset + remain - iter.len();
 
         let val = &mut iter.take(len);
 
@@ -314,18 +374,42 @@ impl Tlv {
         };
 
         if tlv.is_primitive() {
-            tlv.val = Value::Val(val.cloned().collect());
+            let v: Vec<u8> = val.cloned().collect();
+            tlv.val = match parser.options.nested.contains(&tag) {
+                true => parse_nested(&v, parser.options).map_or(Value::Val(v), Value::TlvList),
+                false => Value::Val(v),
+            };
             return Ok(tlv);
         }
 
-        tlv.val = Value::TlvList(vec![]);
-
-        while val.size_hint().0 != 0 {
-            if let Value::TlvList(ref mut children) = tlv.val {
-                children.push(Tlv::from_iter(val)?);
+        let mut children = vec![];
+        parser.path.push(tag);
+
+        while val.len() != 0 {
+            let child_offset = val_offset + len - val.len();
+            match Tlv::from_iter(val, child_offset, parser) {
+                Ok(child) => children.push(child),
+                Err(error) => {
+                    let diagnostics = parser.diagnostics.as_mut().ok_or(error.clone())?;
+                    diagnostics.push(Diagnostic {
+                        offset: child_offset,
+                        error: error.clone(),
+                    });
+
+                    // length of malformed data object is unknown, so the rest is skipped
+                    val.for_each(drop);
+                    let bytes = parser.input[child_offset..val_offset + len].to_vec();
+                    children.push(Tlv {
+                        tag: Tlv::read_tag(&mut bytes.iter()).unwrap_or(0),
+                        val: Value::Invalid { bytes, error },
+                    });
+                    break;
+                }
             }
         }
 
+        parser.path.pop();
+        tlv.val = Value::TlvList(children);
         Ok(tlv)
     }
 
@@ -341,8 +425,124 @@ impl Tlv {
     /// assert_eq!(tlv.len(), 0x02);
     /// ```
     pub fn from_vec(slice: &[u8]) -> Result<Tlv> {
+        Tlv::from_vec_with(slice, &ParseOptions::default())
+    }
+
+    /// Initializes Tlv object from the beginning of [u8] slice and returns it
+    /// together with the rest of slice
+    ///
+    /// Unlike advancing by `len()`, the rest accounts for `00` padding skipped by
+    /// the parser, so it is suitable for reading concatenated data objects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::tlv::*;
+    /// let input = [0x00, 0x70, 0x05, 0x00, 0x00, 0x5A, 0x01, 0x47, 0x5A, 0x01, 0x48];
+    /// let (tlv, rest) = Tlv::from_vec_partial(&input).unwrap();
+    /// assert_eq!(tlv.len(), 5);
+    /// assert_eq!(rest, [0x5A, 0x01, 0x48]);
+    /// ```
+    pub fn from_vec_partial(slice: &[u8]) -> Result<(Tlv, &[u8])> {
+        let options = ParseOptions::default();
+        let mut parser = Parser::new(slice, &options, false);
         let iter = &mut slice.iter();
-        Tlv::from_iter(iter)
+        let tlv = Tlv::from_iter(iter, 0, &mut parser)?;
+        Ok((tlv, &slice[slice.len() - iter.len()..]))
+    }
+
+    /// Initializes Tlv object from [u8] slice using parser options
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::tlv::*;
+    /// // Issuer Application Data carrying TLV encoded data objects
+    /// let input = [0x77, 0x07, 0x9F, 0x10, 0x04, 0xDF, 0x01, 0x01, 0x55];
+    /// let options = ParseOptions::new().nested(0x9F10);
+    /// let tlv = Tlv::from_vec_with(&input, &options).unwrap();
+    ///
+    /// assert_eq!(tlv.find_val("77 / 9F10 / DF01"), Some(&Value::Val(vec![0x55])));
+    /// assert_eq!(tlv.to_vec(), input);
+    /// ```
+    pub fn from_vec_with(slice: &[u8], options: &ParseOptions) -> Result<Tlv> {
+        let mut parser = Parser::new(slice, options, false);
+        Tlv::from_iter(&mut slice.iter(), 0, &mut parser)
+    }
+
+    /// Initializes Tlv object from [u8] slice recovering from malformed children
+    ///
+    /// Unparseable remainder of constructed value is kept as [`Value::Invalid`] child
+    /// and parsing continues with siblings of its parent. Error is returned only if
+    /// header of the outermost data object is malformed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::tlv::*;
+    /// # use tlv_parser::TlvError;
+    /// // the second child of '70' claims more bytes than available
+    /// let input = [0x6F, 0x0B, 0x70, 0x06, 0x5A, 0x01, 0x47, 0x9F, 0x08, 0x05, 0x84, 0x01, 0xA0];
+    /// let (tlv, diagnostics) = Tlv::from_vec_lenient(&input, &ParseOptions::new()).unwrap();
+    ///
+    /// assert_eq!(tlv.find_val("6F / 70 / 5A"), Some(&Value::Val(vec![0x47])));
+    /// assert_eq!(tlv.find_val("6F / 84"), Some(&Value::Val(vec![0xA0])));
+    /// assert_eq!(diagnostics[0].offset, 7);
+    /// assert!(matches!(diagnostics[0].error.innermost(), TlvError::TooShortBody { .. }));
+    /// assert_eq!(tlv.to_vec(), input);
+    /// ```
+    pub fn from_vec_lenient(
+        slice: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Tlv, Vec<Diagnostic>)> {
+        let mut parser = Parser::new(slice, options, true);
+        let tlv = Tlv::from_iter(&mut slice.iter(), 0, &mut parser)?;
+        Ok((tlv, parser.diagnostics.unwrap_or_default()))
+    }
+
+    /// Brings TLV tree to canonical form, so `to_vec` of semantically equal trees
+    /// yields the same bytes
+    ///
+    /// Redundant leading `80` octets are removed from tag numbers, empty values are
+    /// unified, trailing `00` padding kept by lenient parser is stripped and children
+    /// are ordered according to `order`. Length is always encoded in minimal form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::tlv::*;
+    /// let input = [0x70, 0x0A, 0x9F, 0x80, 0x36, 0x81, 0x01, 0x01, 0x5A, 0x01, 0x47, 0x00];
+    /// let (mut tlv, _) = Tlv::from_vec_lenient(&input, &ParseOptions::new()).unwrap();
+    /// tlv.normalize(ChildOrder::ByTag);
+    /// assert_eq!(tlv.to_vec(), [0x70, 0x07, 0x5A, 0x01, 0x47, 0x9F, 0x36, 0x01, 0x01]);
+    /// ```
+    pub fn normalize(&mut self, order: ChildOrder) {
+        self.tag = minimal_tag(self.tag);
+        let primitive = self.is_primitive();
+
+        match self.val {
+            Value::TlvList(ref mut list) => {
+                list.retain(|x| !x.is_padding());
+                list.iter_mut().for_each(|x| x.normalize(order));
+
+                match order {
+                    ChildOrder::Keep => (),
+                    ChildOrder::ByTag => list.sort_by_key(|x| x.tag),
+                    ChildOrder::DerSetOf => list.sort_by_cached_key(|x| x.to_vec()),
+                }
+            }
+            Value::Val(_) | Value::Invalid { .. } => (),
+            Value::Nothing if primitive => self.val = Value::Val(vec![]),
+            Value::Nothing => self.val = Value::TlvList(vec![]),
+        }
+    }
+
+    /// Returns true if TLV is `00` padding kept by lenient parser
+    fn is_padding(&self) -> bool {
+        match self.val {
+            Value::Invalid { ref bytes, .. } => bytes.iter().all(|&x| x == 0),
+            _ => false,
+        }
     }
 
     /// Parses BER-TLV encoded list of tag numbers to vector of tag numbers
@@ -367,6 +567,340 @@ impl Tlv {
     }
 }
 
+/// Options of parser
+///
+/// Primitive data objects listed as nested are parsed as list of TLV data objects
+/// when their value consists of TLV data objects only, so [`Tlv::find_val`] paths
+/// can traverse them. Tag number is kept, so [`Tlv::to_vec`] yields the same bytes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    nested: BTreeSet<Tag>,
+}
+
+impl ParseOptions {
+    /// Returns default options
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Descends into primitive data objects with given tag number
+    pub fn nested(mut self, tag: Tag) -> ParseOptions {
+        self.nested.insert(tag);
+        self
+    }
+}
+
+/// Error recovered by lenient parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Position of malformed data object within input
+    pub offset: usize,
+    pub error: TlvError,
+}
+
+/// Error is shown with its context, which includes offset
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+/// State of single parse
+struct Parser<'a> {
+    input: &'a [u8],
+    options: &'a ParseOptions,
+    /// Recovered errors, `None` in strict mode
+    diagnostics: Option<Vec<Diagnostic>>,
+    /// Tags of constructed data objects being decoded
+    path: Vec<Tag>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a [u8], options: &'a ParseOptions, lenient: bool) -> Parser<'a> {
+        Parser {
+            input,
+            options,
+            diagnostics: lenient.then(Vec::new),
+            path: Vec::new(),
+        }
+    }
+
+    /// Adds position of data object to error
+    ///
+    /// Error in header of the outermost data object is returned as is.
+    fn context(&self, tag: Option<Tag>, offset: usize, error: TlvError) -> TlvError {
+        if self.path.is_empty() && offset == 0 {
+            return error;
+        }
+
+        let mut path = self.path.clone();
+        path.extend(tag);
+
+        TlvError::Context {
+            path,
+            offset,
+            source: Box::new(error),
+        }
+    }
+}
+
+/// Parses value as list of TLV data objects; `None` is returned unless the list
+/// re-encodes to exactly the same bytes
+fn parse_nested(val: &[u8], options: &ParseOptions) -> Option<Vec<Tlv>> {
+    if val.is_empty() {
+        return None;
+    }
+
+    let mut parser = Parser::new(val, options, false);
+    let iter = &mut val.iter();
+    let mut list = Vec::new();
+    while iter.len() != 0 {
+        let offset = val.len() - iter.len();
+        list.push(Tlv::from_iter(iter, offset, &mut parser).ok()?);
+    }
+
+    let encoded: Vec<u8> = list.iter().flat_map(|x| x.to_vec()).collect();
+    (encoded == val).then_some(list)
+}
+
+/// Ordering of children applied by [`Tlv::normalize`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChildOrder {
+    /// Keep original order
+    #[default]
+    Keep,
+    /// Ascending order of tag numbers (stable for repeated tags)
+    ByTag,
+    /// Ascending order of encodings as required for DER `SET OF` (X.690, 11.6)
+    DerSetOf,
+}
+
+/// Decodes length field of BER-TLV data object
+///
+/// Returns length and number of consumed bytes. Only definite form is supported and
+/// encoding must be minimal (X.690, 10.1).
+///
+/// Parser of [`Tlv`] uses the same rules but accepts non-minimal long form,
+/// which ISO/IEC 7816-4 permits.
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::*;
+/// # use tlv_parser::TlvError;
+/// assert_eq!(decode_length(&[0x05, 0xAA]), Ok((5, 1)));
+/// assert_eq!(decode_length(&[0x82, 0x01, 0x00]), Ok((256, 3)));
+///
+/// assert_eq!(decode_length(&[0x80]), Err(TlvError::IndefiniteLength));
+/// assert_eq!(decode_length(&[0xFF]), Err(TlvError::ReservedLength));
+/// assert_eq!(decode_length(&[0x82, 0x01]), Err(TlvError::TruncatedLength));
+/// assert_eq!(decode_length(&[0x81, 0x05]), Err(TlvError::NonMinimalLength));
+/// ```
+pub fn decode_length(data: &[u8]) -> Result<(Length, usize)> {
+    decode(data, true)
+}
+
+fn decode(data: &[u8], minimal: bool) -> Result<(Length, usize)> {
+    let (&first, rest) = data.split_first().ok_or(TlvError::TruncatedLength)?;
+    if first & 0x80 == 0 {
+        return Ok((first as Length, 1));
+    }
+
+    let octets = match first & 0x7F {
+        0 => return Err(TlvError::IndefiniteLength),
+        0x7F => return Err(TlvError::ReservedLength),
+        n => rest.get(..n as usize).ok_or(TlvError::TruncatedLength)?,
+    };
+
+    // leading zero octets don't affect the value, so only significant ones may overflow
+    let significant = &octets[octets.iter().take_while(|&&x| x == 0).count()..];
+    if significant.len() > core::mem::size_of::<Length>() {
+        return Err(TlvError::LengthOverflow);
+    }
+
+    let len = significant
+        .iter()
+        .fold(0, |len: Length, &x| len << 8 | x as Length);
+
+    if minimal && (len < 0x80 || significant.len() != octets.len()) {
+        return Err(TlvError::NonMinimalLength);
+    }
+
+    Ok((len, 1 + octets.len()))
+}
+
+/// Encodes length field of BER-TLV data object in minimal definite form
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::encode_length;
+/// assert_eq!(*encode_length(0x7F), [0x7F]);
+/// assert_eq!(*encode_length(0x80), [0x81, 0x80]);
+/// assert_eq!(*encode_length(0x1234), [0x82, 0x12, 0x34]);
+/// ```
+pub fn encode_length(len: Length) -> Header {
+    let mut out = Header::default();
+    out.push_length(len);
+    out
+}
+
+/// Decodes tag field of BER-TLV data object
+///
+/// Returns tag number and number of consumed bytes. Unlike parser of [`Tlv`],
+/// `00` padding is not skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::decode_tag;
+/// # use tlv_parser::TlvError;
+/// assert_eq!(decode_tag(&[0x9F, 0x02, 0x06]), Ok((0x9F02, 2)));
+/// assert_eq!(decode_tag(&[0xDF, 0x81, 0x20]), Ok((0xDF8120, 3)));
+/// assert_eq!(decode_tag(&[0x9F, 0x81]), Err(TlvError::TruncatedTlv));
+/// ```
+pub fn decode_tag(data: &[u8]) -> Result<(Tag, usize)> {
+    let first = *data.first().ok_or(TlvError::TruncatedTlv)?;
+    if first == 0 {
+        return Err(TlvError::InvalidTagNumber);
+    }
+
+    if first & 0x1F != 0x1F {
+        return Ok((first as Tag, 1));
+    }
+
+    let mut tag = first as Tag;
+    for (i, &x) in data.iter().enumerate().skip(1) {
+        if i >= size_of::<Tag>() {
+            return Err(TlvError::InvalidTagNumber);
+        }
+
+        tag = tag << 8 | x as Tag;
+        if x & 0x80 == 0 {
+            return Ok((tag, i + 1));
+        }
+    }
+
+    Err(TlvError::TruncatedTlv)
+}
+
+/// Encodes tag number
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::encode_tag;
+/// assert_eq!(*encode_tag(0x9F02), [0x9F, 0x02]);
+/// ```
+pub fn encode_tag(tag: Tag) -> Header {
+    let mut out = Header::default();
+    out.push_tag(tag);
+    out
+}
+
+/// Encodes tag and length fields of BER-TLV data object, so value may be
+/// written or hashed separately
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::*;
+/// let header = encode_header(0x9F46, 0x90);
+/// assert_eq!(*header, [0x9F, 0x46, 0x81, 0x90]);
+/// assert_eq!(header.len(), header_len(0x9F46, 0x90));
+/// ```
+pub fn encode_header(tag: Tag, len: Length) -> Header {
+    let mut out = Header::default();
+    out.push_tag(tag);
+    out.push_length(len);
+    out
+}
+
+/// Returns size of tag and length fields of BER-TLV data object
+pub fn header_len(tag: Tag, len: Length) -> usize {
+    encoded_tag_len(tag) + encoded_length_len(len)
+}
+
+/// Encoded tag and/or length fields kept without allocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    buf: [u8; 2 * size_of::<usize>() + 1],
+    len: usize,
+}
+
+impl Default for Header {
+    fn default() -> Header {
+        Header {
+            buf: [0; 2 * size_of::<usize>() + 1],
+            len: 0,
+        }
+    }
+}
+
+impl Header {
+    fn push_tag(&mut self, tag: Tag) {
+        let size = encoded_tag_len(tag);
+        self.push(&tag.to_be_bytes()[size_of::<Tag>() - size..]);
+    }
+
+    fn push_length(&mut self, len: Length) {
+        let size = encoded_length_len(len);
+        if size == 1 {
+            self.push(&[len as u8]);
+        } else {
+            self.push(&[0x80 | (size - 1) as u8]);
+            self.push(&len.to_be_bytes()[size_of::<Length>() + 1 - size..]);
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+}
+
+impl core::ops::Deref for Header {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl AsRef<[u8]> for Header {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// Returns number of bytes of tag number; tag number 0 takes one byte
+fn encoded_tag_len(tag: Tag) -> usize {
+    (size_of::<Tag>() - tag.leading_zeros() as usize / 8).max(1)
+}
+
+fn encoded_length_len(len: Length) -> usize {
+    match len {
+        0..=0x7F => 1,
+        _ => 1 + size_of::<Length>() - len.leading_zeros() as usize / 8,
+    }
+}
+
+/// Removes `80` octets following the first octet of long-form tag number
+fn minimal_tag(tag: Tag) -> Tag {
+    let bytes = (tag as u64).to_be_bytes();
+    let bytes = &bytes[bytes.iter().take_while(|&&x| x == 0).count()..];
+
+    match bytes.split_first() {
+        Some((&first, rest)) if first & 0x1F == 0x1F => {
+            let start = rest.iter().take_while(|&&x| x == 0x80).count();
+            rest[start..]
+                .iter()
+                .fold(first as Tag, |tag, &x| tag << 8 | x as Tag)
+        }
+        _ => tag,
+    }
+}
+
 impl Value {
     /// Returns size of value in bytes
     fn len(&self) -> usize {
@@ -374,6 +908,7 @@ impl Value {
             Value::TlvList(ref list) => list.iter().fold(0, |sum, x| sum + x.len()),
             Value::Val(ref v) => v.len(),
             Value::Nothing => 0,
+            Value::Invalid { ref bytes, .. } => bytes.len(),
         }
     }
 
@@ -383,6 +918,7 @@ impl Value {
             Value::TlvList(ref list) => list.is_empty(),
             Value::Val(ref v) => v.is_empty(),
             Value::Nothing => true,
+            Value::Invalid { ref bytes, .. } => bytes.is_empty(),
         }
     }
 
@@ -390,29 +926,42 @@ impl Value {
     ///
     /// Note: implements only definite form
     pub fn encode_len(&self) -> Vec<u8> {
-        let len = self.len();
-        if len <= 0x7f {
-            return vec![len as u8];
-        }
-
-        let mut out: Vec<u8> = len
-            .to_be_bytes()
-            .iter()
-            .skip_while(|&x| *x == 0)
-            .cloned()
-            .collect();
+        encode_length(self.len()).to_vec()
+    }
 
-        let bytes = out.len() as u8;
-        out.insert(0, 0x80 | bytes);
-        out
+    /// Parses primitive value as list of TLV data objects
+    ///
+    /// Returns `None` if value is not a sequence of well-formed TLV data objects
+    /// re-encoding to the same bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::tlv::*;
+    /// let val = Value::Val(vec![0x86, 0x02, 0x84, 0x24, 0x9F, 0x18, 0x00]);
+    /// let list = val.try_parse_nested().unwrap();
+    /// assert_eq!(list[1].tag(), 0x9F18);
+    ///
+    /// assert!(Value::Val(vec![0x86, 0x05, 0x84]).try_parse_nested().is_none());
+    /// ```
+    pub fn try_parse_nested(&self) -> Option<Vec<Tlv>> {
+        match *self {
+            Value::Val(ref v) => parse_nested(v, &ParseOptions::default()),
+            _ => None,
+        }
     }
 
     /// Returns value as byte array
     pub fn to_vec(&self) -> Vec<u8> {
         match *self {
-            Value::TlvList(ref list) => list.iter().map(|tlv| tlv.to_vec()).flatten().collect(),
+            Value::TlvList(ref list) => {
+                let mut out = Vec::new();
+                list.iter().for_each(|tlv| tlv.encode_into(&mut out));
+                out
+            }
             Value::Val(ref v) => v.clone(),
             Value::Nothing => Vec::new(),
+            Value::Invalid { ref bytes, .. } => bytes.clone(),
         }
     }
 }
@@ -449,7 +998,7 @@ impl fmt::Display for Tlv {
             _ => f.pad("")?,
         }
 
-        self.val.fmt(f)?;
+        fmt::Display::fmt(&self.val, f)?;
         f.pad("")
     }
 }
@@ -479,7 +1028,18 @@ impl fmt::Display for Value {
                 }
                 Ok(())
             }
-            _ => ().fmt(f),
+
+            Value::Invalid {
+                ref bytes,
+                ref error,
+            } => {
+                write!(f, "invalid=")?;
+                for x in bytes {
+                    write!(f, "{:02X}", x)?;
+                }
+                write!(f, " ({})", error)
+            }
+            Value::Nothing => ().fmt(f),
         }
     }
 }
@@ -487,6 +1047,7 @@ impl fmt::Display for Value {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
 
     #[test]
     fn from_vec_test() {
@@ -530,6 +1091,302 @@ mod tests {
         );
     }
 
+    #[test]
+    fn normalize_test() {
+        // trailing padding inside template is kept by lenient parser only
+        let input = [0x70, 0x05, 0x5A, 0x01, 0x47, 0x00, 0x00];
+        assert!(Tlv::from_vec(&input).is_err());
+        let (mut x, _) = Tlv::from_vec_lenient(&input, &ParseOptions::new()).unwrap();
+        assert_eq!(x.to_vec(), input);
+        assert_eq!(x.len(), input.len());
+        x.normalize(ChildOrder::Keep);
+        assert_eq!(x.to_vec(), [0x70, 0x03, 0x5A, 0x01, 0x47]);
+
+        // nested one too, while other malformed remainders are kept
+        let input = [
+            0x70, 0x09, 0x71, 0x05, 0x84, 0x01, 0xA0, 0x00, 0x00, 0x5A, 0x00,
+        ];
+        let (mut x, _) = Tlv::from_vec_lenient(&input, &ParseOptions::new()).unwrap();
+        x.normalize(ChildOrder::ByTag);
+        assert_eq!(
+            x.to_vec(),
+            [0x70, 0x07, 0x5A, 0x00, 0x71, 0x03, 0x84, 0x01, 0xA0]
+        );
+
+        let input = [0x70, 0x05, 0x5A, 0x01, 0x47, 0x00, 0x9F];
+        let (mut x, _) = Tlv::from_vec_lenient(&input, &ParseOptions::new()).unwrap();
+        x.normalize(ChildOrder::Keep);
+        assert_eq!(x.to_vec(), input);
+
+        let a = Tlv::from_vec(&[
+            0x31, 0x0B, 0x04, 0x02, 0x01, 0x02, 0x04, 0x01, 0x01, 0x9F, 0x80, 0x02, 0x00,
+        ])
+        .unwrap();
+        let b = Tlv::from_vec(&[
+            0x31, 0x0A, 0x9F, 0x02, 0x00, 0x04, 0x01, 0x01, 0x04, 0x02, 0x01, 0x02,
+        ])
+        .unwrap();
+
+        let mut x = a.clone();
+        x.normalize(ChildOrder::Keep);
+        assert_eq!(
+            x.to_vec(),
+            [0x31, 0x0A, 0x04, 0x02, 0x01, 0x02, 0x04, 0x01, 0x01, 0x9F, 0x02, 0x00]
+        );
+
+        let (mut x, mut y) = (a.clone(), b.clone());
+        x.normalize(ChildOrder::ByTag);
+        y.normalize(ChildOrder::ByTag);
+        assert_eq!(
+            x.to_vec(),
+            [0x31, 0x0A, 0x04, 0x02, 0x01, 0x02, 0x04, 0x01, 0x01, 0x9F, 0x02, 0x00]
+        );
+        assert_ne!(x, y);
+
+        let (mut x, mut y) = (a, b);
+        x.normalize(ChildOrder::DerSetOf);
+        y.normalize(ChildOrder::DerSetOf);
+        assert_eq!(x, y);
+        assert_eq!(
+            x.to_vec(),
+            [0x31, 0x0A, 0x04, 0x01, 0x01, 0x04, 0x02, 0x01, 0x02, 0x9F, 0x02, 0x00]
+        );
+
+        let mut x = Tlv::new(0x21, Value::Nothing).unwrap();
+        x.normalize(ChildOrder::Keep);
+        assert_eq!(*x.val(), Value::TlvList(vec![]));
+
+        // tag numbers without redundant octets are kept
+        assert_eq!(minimal_tag(0x5FC88001), 0x5FC88001);
+        assert_eq!(minimal_tag(0xDF808120), 0xDF8120);
+        assert_eq!(minimal_tag(0x82), 0x82);
+    }
+
+    #[test]
+    fn nested_test() {
+        // issuer script template with command carrying TLV encoded data
+        let input = [
+            0x71, 0x0D, 0x9F, 0x18, 0x01, 0x01, 0x86, 0x07, 0x84, 0x24, 0x00, 0x00, 0x02, 0x5A,
+            0x00,
+        ];
+        let options = ParseOptions::new().nested(0x86);
+
+        let tlv = Tlv::from_vec_with(&input, &options).unwrap();
+        assert_eq!(tlv.to_vec(), input);
+
+        // value of 86 is not TLV encoded, so it stays primitive
+        assert!(matches!(tlv.find_val("71 / 86"), Some(Value::Val(_))));
+
+        // OCTET STRING wrapping OCTET STRING
+        let input = [0x04, 0x05, 0x04, 0x03, 0x02, 0x01, 0x05];
+        let tlv = Tlv::from_vec_with(&input, &ParseOptions::new().nested(0x04)).unwrap();
+        assert_eq!(tlv.find_val("04 / 04 / 02"), Some(&Value::Val(vec![0x05])));
+        assert_eq!(tlv.to_vec(), input);
+        assert_eq!(tlv.len(), input.len());
+
+        let tlv = Tlv::from_vec(&input).unwrap();
+        assert!(tlv.find_val("04 / 04").is_none());
+        assert_eq!(tlv.val().try_parse_nested().unwrap().len(), 1);
+
+        // non-minimal length and padding are not descended into
+        assert!(Value::Val(vec![0x5A, 0x81, 0x01, 0x00])
+            .try_parse_nested()
+            .is_none());
+        assert!(Value::Val(vec![0x00, 0x5A, 0x00])
+            .try_parse_nested()
+            .is_none());
+        assert!(Value::Nothing.try_parse_nested().is_none());
+    }
+
+    #[test]
+    fn length_test() {
+        for len in [0, 0x7F, 0x80, 0xFF, 0x100, 0xFFFF, 0x10000, usize::MAX] {
+            let encoded = encode_length(len);
+            assert_eq!(decode_length(&encoded), Ok((len, encoded.len())));
+        }
+
+        let mut overflow = vec![0x80 | (core::mem::size_of::<Length>() as u8 + 1), 0x01];
+        overflow.resize(overflow.len() + core::mem::size_of::<Length>(), 0x00);
+        assert_eq!(decode_length(&overflow), Err(TlvError::LengthOverflow));
+        assert_eq!(decode_length(&[]), Err(TlvError::TruncatedLength));
+        assert_eq!(
+            decode_length(&[0x82, 0x00, 0x80]),
+            Err(TlvError::NonMinimalLength)
+        );
+
+        // parser accepts non-minimal long form
+        let tlv = Tlv::from_vec(&[0x5A, 0x82, 0x00, 0x01, 0x47]).unwrap();
+        assert_eq!(tlv.to_vec(), [0x5A, 0x01, 0x47]);
+
+        let error = |input: &[u8]| Tlv::from_vec(input).unwrap_err().innermost().clone();
+        assert_eq!(error(&[0x5A, 0x80, 0x00, 0x00]), TlvError::IndefiniteLength);
+        assert_eq!(error(&[0x5A, 0xFF]), TlvError::ReservedLength);
+        assert_eq!(error(&[0x5A, 0x82, 0x01]), TlvError::TruncatedLength);
+        overflow.insert(0, 0x5A);
+        assert_eq!(error(&overflow), TlvError::LengthOverflow);
+    }
+
+    #[test]
+    fn deep_tree_test() {
+        let mut tlv = Tlv::new(0x5A, Value::Val(vec![0x47; 0x90])).unwrap();
+        for i in 0..200 {
+            let sibling = Tlv::new(0x9F02, Value::Val(vec![i as u8])).unwrap();
+            tlv = Tlv::new(0xBF0C, Value::TlvList(vec![sibling, tlv])).unwrap();
+        }
+
+        let encoded = tlv.to_vec();
+        assert_eq!(tlv.len(), encoded.len());
+        assert_eq!(Tlv::from_vec(&encoded).unwrap(), tlv);
+        assert_eq!(tlv.val().to_vec(), encoded[tlv.len() - tlv.val().len()..]);
+    }
+
+    #[test]
+    fn header_codec_test() {
+        let tags: [(Tag, &[u8]); 5] = [
+            (0x01, &[0x01]),
+            (0x70, &[0x70]),
+            (0x9F02, &[0x9F, 0x02]),
+            (0xDF8120, &[0xDF, 0x81, 0x20]),
+            (0xFF818220, &[0xFF, 0x81, 0x82, 0x20]),
+        ];
+        let lengths: [(Length, &[u8]); 10] = [
+            (0, &[0x00]),
+            (0x7F, &[0x7F]),
+            (0x80, &[0x81, 0x80]),
+            (0xFF, &[0x81, 0xFF]),
+            (0x100, &[0x82, 0x01, 0x00]),
+            (0xFFFF, &[0x82, 0xFF, 0xFF]),
+            (0x10000, &[0x83, 0x01, 0x00, 0x00]),
+            (0xFFFFFF, &[0x83, 0xFF, 0xFF, 0xFF]),
+            (0x1000000, &[0x84, 0x01, 0x00, 0x00, 0x00]),
+            (0xFFFFFFFF, &[0x84, 0xFF, 0xFF, 0xFF, 0xFF]),
+        ];
+
+        for (tag, tag_bytes) in tags {
+            assert_eq!(*encode_tag(tag), *tag_bytes);
+            assert_eq!(decode_tag(tag_bytes), Ok((tag, tag_bytes.len())));
+
+            for (len, len_bytes) in lengths {
+                assert_eq!(*encode_length(len), *len_bytes);
+                assert_eq!(decode_length(len_bytes), Ok((len, len_bytes.len())));
+
+                let header = encode_header(tag, len);
+                assert_eq!(*header, [tag_bytes, len_bytes].concat());
+                assert_eq!(header.len(), header_len(tag, len));
+
+                let (decoded_tag, tag_size) = decode_tag(&header).unwrap();
+                let (decoded_len, len_size) = decode_length(&header[tag_size..]).unwrap();
+                assert_eq!((decoded_tag, decoded_len), (tag, len));
+                assert_eq!(tag_size + len_size, header.len());
+            }
+        }
+
+        let max = encode_header(usize::MAX, usize::MAX);
+        assert_eq!(max.len(), header_len(usize::MAX, usize::MAX));
+
+        assert_eq!(*encode_tag(0), [0x00]);
+        assert_eq!(decode_tag(&[]), Err(TlvError::TruncatedTlv));
+        assert_eq!(decode_tag(&[0x00]), Err(TlvError::InvalidTagNumber));
+        assert_eq!(decode_tag(&[0x1F, 0x81]), Err(TlvError::TruncatedTlv));
+        assert_eq!(
+            decode_tag(&[0x1F, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x08]),
+            Err(TlvError::InvalidTagNumber)
+        );
+        assert_eq!(
+            Tlv::from_vec(&[0x1F, 0x81, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x08, 0x00])
+                .unwrap_err()
+                .innermost(),
+            &TlvError::InvalidTagNumber
+        );
+    }
+
+    #[test]
+    fn error_context_test() {
+        let mut input = vec![0x70, 0x3B];
+        input.extend_from_slice(&[0x5A, 0x08, 0x47, 0x61, 0x73, 0x90, 0x01, 0x01, 0x00, 0x10]);
+        input.extend_from_slice(&[0x5F, 0x24, 0x03, 0x25, 0x12, 0x31]);
+        input.extend_from_slice(&[0x9F, 0x46, 0x81, 0x80]);
+        input.extend_from_slice(&[0xAB; 39]);
+
+        let error = Tlv::from_vec(&input).unwrap_err();
+        assert_eq!(
+            error,
+            TlvError::Context {
+                path: vec![0x70, 0x9F46],
+                offset: 0x12,
+                source: Box::new(TlvError::TooShortBody {
+                    expected: 128,
+                    found: 39
+                }),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "in 70 / 9F46 at offset 0x12: Too short body: expected 128, found 39"
+        );
+
+        // length of nested template is missing
+        let error = Tlv::from_vec(&[0x6F, 0x01, 0xA5]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "in 6F / A5 at offset 0x02: Truncated length field"
+        );
+
+        // errors in header of the outermost data object are not wrapped
+        assert_eq!(Tlv::from_vec(&[0x00, 0x00]), Err(TlvError::TruncatedTlv));
+        assert_eq!(
+            Tlv::from_vec(&[0x70, 0x05, 0x5A]),
+            Err(TlvError::TooShortBody {
+                expected: 5,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Tlv::from_vec(&[0x70, 0x80]),
+            Err(TlvError::IndefiniteLength)
+        );
+    }
+
+    #[test]
+    fn lenient_test() {
+        // the second child of '70' claims more bytes than left, '9F36' follows the record
+        let input = [
+            0x77, 0x0D, 0x70, 0x06, 0x5A, 0x01, 0x47, 0xDF, 0x01, 0x05, 0x9F, 0x36, 0x02, 0x00,
+            0x01,
+        ];
+        let error = Tlv::from_vec(&input).unwrap_err();
+        assert!(matches!(error.innermost(), TlvError::TooShortBody { .. }));
+
+        let (tlv, diagnostics) = Tlv::from_vec_lenient(&input, &ParseOptions::new()).unwrap();
+        assert_eq!(diagnostics, [Diagnostic { offset: 7, error }]);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "in 77 / 70 / DF01 at offset 0x07: Too short body: expected 5, found 0"
+        );
+
+        assert_eq!(tlv.find_val("77 / 70 / 5A"), Some(&Value::Val(vec![0x47])));
+        assert_eq!(
+            tlv.find_val("77 / 9F36"),
+            Some(&Value::Val(vec![0x00, 0x01]))
+        );
+        assert_eq!(
+            tlv.find_val("77 / 70 / DF01"),
+            Some(&Value::Invalid {
+                bytes: vec![0xDF, 0x01, 0x05],
+                error: diagnostics[0].error.clone(),
+            })
+        );
+        assert_eq!(tlv.to_vec(), input);
+        assert_eq!(tlv.len(), input.len());
+
+        // malformed outermost header can't be recovered
+        assert!(Tlv::from_vec_lenient(&[0x9F], &ParseOptions::new()).is_err());
+
+        let (tlv, diagnostics) = Tlv::from_vec_lenient(&input[4..7], &ParseOptions::new()).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(tlv, Tlv::from_vec(&input[4..7]).unwrap());
+    }
+
     #[test]
     fn to_vec_test() {
         let tlv = Tlv {
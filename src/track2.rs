@@ -0,0 +1,441 @@
+//! Track 2 Equivalent Data (tag `57`) and PAN (tag `5A`) handling.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::dol::DolSource;
+use crate::tlv::{Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Tag number of Track 2 Equivalent Data
+pub const TRACK2_TAG: usize = 0x57;
+
+/// Tag number of Application Primary Account Number
+pub const PAN_TAG: usize = 0x5A;
+
+/// Tag number of Application Expiration Date
+pub const EXPIRY_TAG: usize = 0x5F24;
+
+/// Track 2 Equivalent Data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track2 {
+    pan: String,
+    expiry: String,
+    service_code: String,
+    discretionary: String,
+}
+
+impl Track2 {
+    /// Creates Track 2 data from its fields, all of them must consist of decimal digits
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::track2::Track2;
+    /// let track2 = Track2::new("4761739001010010", "2212", "201", "1").unwrap();
+    /// assert_eq!(track2.to_vec(), vec![
+    ///     0x47, 0x61, 0x73, 0x90, 0x01, 0x01, 0x00, 0x10, 0xD2, 0x21, 0x22, 0x01, 0x1F,
+    /// ]);
+    /// ```
+    pub fn new(pan: &str, expiry: &str, service_code: &str, discretionary: &str) -> Result<Track2> {
+        let valid = is_digits(pan)
+            && !pan.is_empty()
+            && pan.len() <= 19
+            && is_digits(expiry)
+            && expiry.len() == 4
+            && is_digits(service_code)
+            && service_code.len() == 3
+            && is_digits(discretionary);
+
+        if !valid {
+            return Err(TlvError::InvalidValue {
+                tag_number: TRACK2_TAG,
+            });
+        }
+
+        Ok(Track2 {
+            pan: pan.into(),
+            expiry: expiry.into(),
+            service_code: service_code.into(),
+            discretionary: discretionary.into(),
+        })
+    }
+
+    /// Parses value of data object `57`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tlv_parser::track2::Track2;
+    /// let track2 = Track2::from_vec(&[
+    ///     0x37, 0x42, 0x45, 0x00, 0x17, 0x41, 0x00, 0x7D, 0x18, 0x12, 0x70, 0x21, 0x31, 0x01,
+    ///     0x23, 0x45, 0x00, 0x00, 0x0F,
+    /// ]).unwrap();
+    /// assert_eq!(track2.pan(), "374245001741007");
+    /// assert_eq!(track2.expiry(), "1812");
+    /// assert_eq!(track2.service_code(), "702");
+    /// assert_eq!(track2.discretionary(), "13101234500000");
+    /// ```
+    pub fn from_vec(data: &[u8]) -> Result<Track2> {
+        let mut nibbles: Vec<u8> = data.iter().flat_map(|x| [x >> 4, x & 0x0F]).collect();
+
+        // single 'F' pads odd number of nibbles
+        if nibbles.last() == Some(&0x0F) {
+            nibbles.pop();
+        }
+
+        let sep = nibbles
+            .iter()
+            .position(|&x| x == 0x0D)
+            .ok_or(TlvError::InvalidValue {
+                tag_number: TRACK2_TAG,
+            })?;
+        let (pan, rest) = (&nibbles[..sep], &nibbles[sep + 1..]);
+
+        if rest.len() < 7 || rest.iter().any(|&x| x > 9) {
+            return Err(TlvError::InvalidValue {
+                tag_number: TRACK2_TAG,
+            });
+        }
+
+        Track2::new(
+            &to_digits(pan)?,
+            &to_digits(&rest[..4])?,
+            &to_digits(&rest[4..7])?,
+            &to_digits(&rest[7..])?,
+        )
+    }
+
+    /// Parses data object `57`
+    pub fn from_tlv(tlv: &Tlv) -> Result<Track2> {
+        if tlv.tag() != TRACK2_TAG {
+            return Err(TlvError::InvalidTagNumber);
+        }
+
+        match *tlv.val() {
+            Value::Val(ref v) => Track2::from_vec(v),
+            _ => Err(TlvError::ValExpected {
+                tag_number: TRACK2_TAG,
+            }),
+        }
+    }
+
+    /// Returns Primary Account Number
+    pub fn pan(&self) -> &str {
+        &self.pan
+    }
+
+    /// Returns expiration date as `YYMM`
+    pub fn expiry(&self) -> &str {
+        &self.expiry
+    }
+
+    /// Returns service code
+    pub fn service_code(&self) -> &str {
+        &self.service_code
+    }
+
+    /// Returns discretionary data
+    pub fn discretionary(&self) -> &str {
+        &self.discretionary
+    }
+
+    /// Returns Track 2 Equivalent Data encoded as array of bytes
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut nibbles: Vec<u8> = self.pan.bytes().map(|x| x - b'0').collect();
+        nibbles.push(0x0D);
+        nibbles.extend(
+            self.expiry
+                .bytes()
+                .chain(self.service_code.bytes())
+                .chain(self.discretionary.bytes())
+                .map(|x| x - b'0'),
+        );
+
+        pack(&nibbles)
+    }
+
+    /// Returns primitive TLV `57`
+    pub fn to_tlv(&self) -> Tlv {
+        Tlv::new(TRACK2_TAG, Value::Val(self.to_vec())).expect("Track 2 tag is primitive")
+    }
+}
+
+/// Prints Track 2 data with masked PAN and without discretionary data
+impl fmt::Display for Track2 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}D{}{}",
+            mask_pan(&self.pan),
+            self.expiry,
+            self.service_code
+        )
+    }
+}
+
+/// Inconsistency found between data objects related to PAN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// PAN in `57` is different from `5A`
+    PanMismatch,
+    /// Expiration date in `57` is different from `5F24`
+    ExpiryMismatch,
+    /// PAN has invalid check digit
+    InvalidLuhn,
+}
+
+/// Checks consistency of Track 2 Equivalent Data, PAN and Application Expiration Date
+///
+/// Missing data objects are not reported. Both `5A` and `57` are checked for valid Luhn.
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::tlv::Tlv;
+/// # use tlv_parser::track2::*;
+/// let record = Tlv::from_vec(&[
+///     0x70, 0x15, 0x5A, 0x08, 0x47, 0x61, 0x73, 0x90, 0x01, 0x01, 0x00, 0x10,
+///     0x5F, 0x24, 0x03, 0x22, 0x11, 0x30, 0x9F, 0x07, 0x02, 0xFF, 0x00,
+/// ]).unwrap();
+/// assert_eq!(check_consistency(&record).unwrap(), vec![]);
+/// ```
+pub fn check_consistency<S: DolSource + ?Sized>(source: &S) -> Result<Vec<Inconsistency>> {
+    let mut out = Vec::new();
+
+    let track2 = match source.dol_value(TRACK2_TAG) {
+        Some(v) => Some(Track2::from_vec(v)?),
+        None => None,
+    };
+
+    let pan = match source.dol_value(PAN_TAG) {
+        Some(v) => Some(pan_from_cn(v)?),
+        None => None,
+    };
+
+    let luhn_ok = track2.iter().all(|x| luhn_check(x.pan())) && pan.iter().all(|x| luhn_check(x));
+    if !luhn_ok {
+        out.push(Inconsistency::InvalidLuhn);
+    }
+
+    if let (Some(track2), Some(pan)) = (&track2, &pan) {
+        if track2.pan() != pan {
+            out.push(Inconsistency::PanMismatch);
+        }
+    }
+
+    if let (Some(track2), Some(expiry)) = (&track2, source.dol_value(EXPIRY_TAG)) {
+        let expiry: Vec<u8> = expiry
+            .iter()
+            .take(2)
+            .flat_map(|x| [x >> 4, x & 0x0F])
+            .collect();
+        let same = expiry.len() == 4
+            && expiry
+                .iter()
+                .zip(track2.expiry().bytes())
+                .all(|(&a, b)| a == b - b'0');
+        if !same {
+            out.push(Inconsistency::ExpiryMismatch);
+        }
+    }
+
+    Ok(out)
+}
+
+/// Decodes PAN in compressed numeric format (value of `5A`)
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::track2::pan_from_cn;
+/// let pan = pan_from_cn(&[0x37, 0x42, 0x45, 0x00, 0x17, 0x41, 0x00, 0x7F]).unwrap();
+/// assert_eq!(pan, "374245001741007");
+/// ```
+pub fn pan_from_cn(data: &[u8]) -> Result<String> {
+    let nibbles: Vec<u8> = data
+        .iter()
+        .flat_map(|x| [x >> 4, x & 0x0F])
+        .take_while(|&x| x != 0x0F)
+        .collect();
+
+    let pan = to_digits(&nibbles).map_err(|_| TlvError::InvalidValue {
+        tag_number: PAN_TAG,
+    })?;
+
+    if pan.is_empty() || pan.len() > 19 {
+        return Err(TlvError::InvalidValue {
+            tag_number: PAN_TAG,
+        });
+    }
+
+    Ok(pan)
+}
+
+/// Encodes PAN in compressed numeric format
+///
+/// Note: non-digit characters are not validated
+pub fn pan_to_cn(pan: &str) -> Vec<u8> {
+    let nibbles: Vec<u8> = pan.bytes().map(|x| x.wrapping_sub(b'0') & 0x0F).collect();
+    pack(&nibbles)
+}
+
+/// Returns true if PAN has valid Luhn check digit
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::track2::luhn_check;
+/// assert!(luhn_check("374245001741007"));
+/// assert!(!luhn_check("374245001741008"));
+/// ```
+pub fn luhn_check(pan: &str) -> bool {
+    if pan.is_empty() || !is_digits(pan) {
+        return false;
+    }
+
+    let sum: u32 = pan
+        .bytes()
+        .rev()
+        .map(|x| (x - b'0') as u32)
+        .enumerate()
+        .map(|(i, d)| match (i % 2 == 1, d * 2) {
+            (true, x) if x > 9 => x - 9,
+            (true, x) => x,
+            (false, _) => d,
+        })
+        .sum();
+
+    sum % 10 == 0
+}
+
+/// Masks PAN for logging keeping the first 6 and the last 4 digits
+///
+/// PANs shorter than 15 digits keep only the last 4 digits, so that at least
+/// 5 digits are always masked in the middle of longer ones.
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::track2::mask_pan;
+/// assert_eq!(mask_pan("374245001741007"), "374245*****1007");
+/// ```
+pub fn mask_pan(pan: &str) -> String {
+    let len = pan.chars().count();
+    let head = if len >= 15 { 6 } else { 0 };
+    let tail = len.saturating_sub(4);
+
+    pan.chars()
+        .enumerate()
+        .map(|(i, x)| if i < head || i >= tail { x } else { '*' })
+        .collect()
+}
+
+fn is_digits(s: &str) -> bool {
+    s.bytes().all(|x| x.is_ascii_digit())
+}
+
+/// Converts decimal nibbles into string
+fn to_digits(nibbles: &[u8]) -> Result<String> {
+    nibbles
+        .iter()
+        .map(|&x| {
+            if x <= 9 {
+                Ok((b'0' + x) as char)
+            } else {
+                Err(TlvError::InvalidValue {
+                    tag_number: TRACK2_TAG,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Packs nibbles into bytes padding with 'F'
+fn pack(nibbles: &[u8]) -> Vec<u8> {
+    nibbles
+        .chunks(2)
+        .map(|x| (x[0] << 4) | x.get(1).copied().unwrap_or(0x0F))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    // READ RECORD response from Amex CDA trace
+    const RECORD: [u8; 79] = [
+        0x70, 0x4D, 0x57, 0x13, 0x37, 0x42, 0x45, 0x00, 0x17, 0x41, 0x00, 0x7D, 0x18, 0x12, 0x70,
+        0x21, 0x31, 0x01, 0x23, 0x45, 0x00, 0x00, 0x0F, 0x5F, 0x20, 0x1A, 0x45, 0x50, 0x61, 0x79,
+        0x20, 0x54, 0x45, 0x53, 0x54, 0x20, 0x43, 0x41, 0x52, 0x44, 0x20, 0x20, 0x20, 0x20, 0x20,
+        0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x9F, 0x5A, 0x08, 0x54, 0x45, 0x53, 0x54, 0x43,
+        0x41, 0x52, 0x44, 0x9F, 0x5B, 0x0D, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39,
+        0x30, 0x31, 0x32, 0x33,
+    ];
+
+    #[test]
+    fn track2_test() {
+        let record = Tlv::from_vec(&RECORD).unwrap();
+        let tlv = record.find_val("70 / 57").unwrap();
+        let track2 = Track2::from_vec(&tlv.to_vec()).unwrap();
+
+        assert_eq!(track2.pan(), "374245001741007");
+        assert_eq!(track2.to_string(), "374245*****1007D1812702");
+        assert_eq!(track2.to_vec(), tlv.to_vec());
+
+        // missing separator
+        assert!(Track2::from_vec(&[0x37, 0x42, 0x45]).is_err());
+        // too short after separator
+        assert!(Track2::from_vec(&[0x37, 0x4D, 0x18, 0x12, 0x7F]).is_err());
+        // non-decimal digits
+        assert!(Track2::from_vec(&[0x3A, 0x4D, 0x18, 0x12, 0x70, 0x21]).is_err());
+        assert!(Track2::new("3742", "181", "702", "").is_err());
+    }
+
+    #[test]
+    fn consistency_test() {
+        let record = Tlv::from_vec(&RECORD).unwrap();
+        assert_eq!(check_consistency(&record).unwrap(), vec![]);
+
+        let records = [
+            record,
+            Tlv::new(PAN_TAG, Value::Val(pan_to_cn("374245001741015"))).unwrap(),
+            Tlv::new(EXPIRY_TAG, Value::Val(vec![0x18, 0x11, 0x30])).unwrap(),
+        ];
+        assert_eq!(
+            check_consistency(&records[..]).unwrap(),
+            vec![Inconsistency::PanMismatch, Inconsistency::ExpiryMismatch]
+        );
+
+        let records = [Tlv::new(PAN_TAG, Value::Val(pan_to_cn("374245001741008"))).unwrap()];
+        assert_eq!(
+            check_consistency(&records[..]).unwrap(),
+            vec![Inconsistency::InvalidLuhn]
+        );
+    }
+
+    #[test]
+    fn pan_test() {
+        assert_eq!(
+            pan_to_cn("374245001741007"),
+            [0x37, 0x42, 0x45, 0x00, 0x17, 0x41, 0x00, 0x7F]
+        );
+        assert_eq!(pan_to_cn("4761739001010010").len(), 8);
+        assert!(pan_from_cn(&[0xFF]).is_err());
+        assert!(pan_from_cn(&[0x4A, 0x11]).is_err());
+
+        assert!(luhn_check("4761739001010010"));
+        assert!(!luhn_check(""));
+        assert!(!luhn_check("47617390010100a0"));
+
+        assert_eq!(mask_pan("4761739001010010"), "476173******0010");
+        assert_eq!(mask_pan("374245001741007"), "374245*****1007");
+        assert_eq!(mask_pan("4000000000006"), "*********0006");
+        assert_eq!(mask_pan("12345678901"), "*******8901");
+        assert_eq!(mask_pan("1234567890"), "******7890");
+        assert_eq!(mask_pan("123"), "123");
+    }
+}
@@ -0,0 +1,702 @@
+//! X.509 certificates and PKCS #10 certification requests.
+//!
+//! DER encoded structure is walked into typed view, see RFC 5280, section 4.1
+//! and RFC 2986, section 4. Signed part is borrowed from input, so signature
+//! may be checked without re-encoding.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::asn1::{self, BitString, DateTime, SEQUENCE_TAG, SET_TAG};
+use crate::oid::{self, Oid};
+use crate::tlv::{Tag, Tlv, Value};
+use crate::{Result, TlvError};
+
+/// Tag number of explicit version of certificate
+const VERSION_TAG: Tag = 0xA0;
+
+/// Tag number of attributes of certification request
+const ATTRIBUTES_TAG: Tag = 0xA0;
+
+/// Tag number of issuer unique identifier
+const ISSUER_UID_TAG: Tag = 0x81;
+
+/// Tag number of subject unique identifier
+const SUBJECT_UID_TAG: Tag = 0x82;
+
+/// Tag number of extensions
+const EXTENSIONS_TAG: Tag = 0xA3;
+
+/// Algorithm and its optional parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlgorithmIdentifier {
+    pub oid: Oid,
+    pub parameters: Option<Tlv>,
+}
+
+impl AlgorithmIdentifier {
+    fn from_tlv(tlv: &Tlv) -> Result<AlgorithmIdentifier> {
+        let mut fields = Fields::new(tlv, SEQUENCE_TAG)?;
+        let oid = asn1::decode_oid(fields.next()?)?;
+        let parameters = fields.rest().first().cloned();
+
+        Ok(AlgorithmIdentifier { oid, parameters })
+    }
+}
+
+/// Attribute of distinguished name, e.g. `CN=Terminal CA`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeTypeAndValue {
+    pub oid: Oid,
+    pub value: Tlv,
+}
+
+impl AttributeTypeAndValue {
+    /// Returns value of string types (UTF8String, PrintableString, IA5String)
+    pub fn value_str(&self) -> Option<&str> {
+        asn1::decode_utf8_string(&self.value)
+            .or_else(|_| asn1::decode_printable_string(&self.value))
+            .or_else(|_| asn1::decode_ia5_string(&self.value))
+            .ok()
+    }
+}
+
+/// Distinguished name as sequence of relative distinguished names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name {
+    pub rdns: Vec<Vec<AttributeTypeAndValue>>,
+}
+
+impl Name {
+    fn from_tlv(tlv: &Tlv) -> Result<Name> {
+        let rdns = Fields::new(tlv, SEQUENCE_TAG)?
+            .rest()
+            .iter()
+            .map(|rdn| {
+                Fields::new(rdn, SET_TAG)?
+                    .rest()
+                    .iter()
+                    .map(|atv| {
+                        let mut fields = Fields::new(atv, SEQUENCE_TAG)?;
+                        Ok(AttributeTypeAndValue {
+                            oid: asn1::decode_oid(fields.next()?)?,
+                            value: fields.next()?.clone(),
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Name { rdns })
+    }
+
+    /// Returns iterator over attributes in encoding order
+    pub fn iter(&self) -> impl Iterator<Item = &AttributeTypeAndValue> {
+        self.rdns.iter().flatten()
+    }
+
+    /// Returns string value of the first attribute of given type
+    pub fn find(&self, oid: &[u64]) -> Option<&str> {
+        self.iter()
+            .find(|x| x.oid == oid)
+            .and_then(|x| x.value_str())
+    }
+}
+
+/// Formats attributes in encoding order, e.g. `C=DE, O=Test HSM, CN=Terminal CA`;
+/// values of non-string types are shown as `#` and hex of encoding
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, atv) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+
+            match SHORT_NAMES.iter().find(|&&(arcs, _)| atv.oid == arcs) {
+                Some(&(_, name)) => write!(f, "{}=", name)?,
+                None => write!(f, "{}=", atv.oid)?,
+            }
+
+            match atv.value_str() {
+                Some(s) => write!(f, "{}", s)?,
+                None => {
+                    write!(f, "#")?;
+                    atv.value
+                        .to_vec()
+                        .iter()
+                        .try_for_each(|x| write!(f, "{:02X}", x))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+const SHORT_NAMES: &[(&[u64], &str)] = &[
+    (oid::COMMON_NAME, "CN"),
+    (oid::SERIAL_NUMBER, "serialNumber"),
+    (oid::COUNTRY_NAME, "C"),
+    (oid::LOCALITY_NAME, "L"),
+    (oid::STATE_OR_PROVINCE_NAME, "ST"),
+    (oid::ORGANIZATION_NAME, "O"),
+    (oid::ORGANIZATIONAL_UNIT_NAME, "OU"),
+    (oid::EMAIL_ADDRESS, "emailAddress"),
+];
+
+/// Validity period of certificate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Validity {
+    pub not_before: DateTime,
+    pub not_after: DateTime,
+}
+
+impl Validity {
+    /// Returns true if certificate is valid at given time
+    pub fn contains(&self, time: &DateTime) -> bool {
+        self.not_before <= *time && *time <= self.not_after
+    }
+}
+
+/// Subject public key and its algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubjectPublicKeyInfo {
+    pub algorithm: AlgorithmIdentifier,
+    pub public_key: BitString,
+}
+
+impl SubjectPublicKeyInfo {
+    fn from_tlv(tlv: &Tlv) -> Result<SubjectPublicKeyInfo> {
+        let mut fields = Fields::new(tlv, SEQUENCE_TAG)?;
+
+        Ok(SubjectPublicKeyInfo {
+            algorithm: AlgorithmIdentifier::from_tlv(fields.next()?)?,
+            public_key: asn1::decode_bit_string(fields.next()?)?,
+        })
+    }
+}
+
+/// Certificate extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    pub oid: Oid,
+    pub critical: bool,
+    /// DER encoding of extension value
+    pub value: Vec<u8>,
+}
+
+impl Extension {
+    fn from_tlv(tlv: &Tlv) -> Result<Extension> {
+        let mut fields = Fields::new(tlv, SEQUENCE_TAG)?;
+        let oid = asn1::decode_oid(fields.next()?)?;
+        let critical = match fields.optional(asn1::BOOLEAN_TAG) {
+            Some(x) => asn1::decode_bool(x)?,
+            None => false,
+        };
+
+        Ok(Extension {
+            oid,
+            critical,
+            value: asn1::decode_octet_string(fields.next()?)?.to_vec(),
+        })
+    }
+
+    fn list(tlv: &Tlv) -> Result<Vec<Extension>> {
+        Fields::new(tlv, SEQUENCE_TAG)?
+            .rest()
+            .iter()
+            .map(Extension::from_tlv)
+            .collect()
+    }
+}
+
+/// X.509 certificate
+///
+/// # Examples
+///
+/// ```
+/// # use tlv_parser::x509::{decode_pem, Certificate};
+/// # use tlv_parser::oid;
+/// let pem = decode_pem(
+///     "-----BEGIN CERTIFICATE-----
+/// MIIBXjCCAQSgAwIBAgIFEjRWeJAwCgYIKoZIzj0EAwIwNjELMAkGA1UEBhMCREUx
+/// ETAPBgNVBAoMCFRlc3QgSFNNMRQwEgYDVQQDDAtUZXJtaW5hbCBDQTAgFw0yNDAx
+/// MDEwMDAwMDBaGA8yMDU0MDEwMTAwMDAwMFowFjEUMBIGA1UEAwwLdGVybWluYWwt
+/// MDEwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAQCBdQtj7MUg/MszT+j/lcyunQc
+/// 1uiX86Oyq0Da2HEKXXWbk50jG0tfXclujKSdkiiPTmnnpoRm2ggXxPQiFM63ox0w
+/// GzAMBgNVHRMBAf8EAjAAMAsGA1UdDwQEAwIHgDAKBggqhkjOPQQDAgNIADBFAiEA
+/// 2XXWeZ+PqosjO1S1gjhz/W/9/pJhx/A9CElGaqgLTeoCICSa3Uwy894jelkc4Y5/
+/// WeR8KXwRuNa8qKY7Ptny5csJ
+/// -----END CERTIFICATE-----",
+/// ).unwrap();
+///
+/// let cert = Certificate::from_der(&pem[0].data).unwrap();
+/// assert_eq!(cert.version, 3);
+/// assert_eq!(cert.issuer.to_string(), "C=DE, O=Test HSM, CN=Terminal CA");
+/// assert_eq!(cert.subject.find(oid::COMMON_NAME), Some("terminal-01"));
+/// assert_eq!(cert.signature_algorithm.oid, oid::ECDSA_WITH_SHA256);
+/// assert_eq!(cert.tbs.len(), 264);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate<'a> {
+    /// DER encoding of TBSCertificate, input of signature
+    pub tbs: &'a [u8],
+    /// Version number: 1, 2 or 3
+    pub version: u8,
+    /// Two's complement octets of serial number
+    pub serial: Vec<u8>,
+    /// Signature algorithm declared inside of TBSCertificate
+    pub tbs_signature_algorithm: AlgorithmIdentifier,
+    pub issuer: Name,
+    pub validity: Validity,
+    pub subject: Name,
+    pub spki: SubjectPublicKeyInfo,
+    pub extensions: Vec<Extension>,
+    pub signature_algorithm: AlgorithmIdentifier,
+    pub signature: BitString,
+}
+
+impl<'a> Certificate<'a> {
+    /// Parses DER encoded certificate
+    pub fn from_der(data: &'a [u8]) -> Result<Certificate<'a>> {
+        let tbs = signed_part(data)?;
+        let tlv = Tlv::from_vec(data)?;
+
+        let mut outer = Fields::new(&tlv, SEQUENCE_TAG)?;
+        let mut fields = Fields::new(outer.next()?, SEQUENCE_TAG)?;
+
+        let version = match fields.optional(VERSION_TAG) {
+            Some(x) => match asn1::decode_i64(Fields::new(x, VERSION_TAG)?.next()?)? {
+                v @ 0..=2 => v as u8 + 1,
+                _ => return Err(invalid(VERSION_TAG)),
+            },
+            None => 1,
+        };
+
+        let serial = asn1::decode_integer_bytes(fields.next()?)?.to_vec();
+        let tbs_signature_algorithm = AlgorithmIdentifier::from_tlv(fields.next()?)?;
+        let issuer = Name::from_tlv(fields.next()?)?;
+
+        let mut times = Fields::new(fields.next()?, SEQUENCE_TAG)?;
+        let validity = Validity {
+            not_before: decode_time(times.next()?)?,
+            not_after: decode_time(times.next()?)?,
+        };
+        times.end()?;
+
+        let subject = Name::from_tlv(fields.next()?)?;
+        let spki = SubjectPublicKeyInfo::from_tlv(fields.next()?)?;
+
+        fields.optional(ISSUER_UID_TAG);
+        fields.optional(SUBJECT_UID_TAG);
+
+        let extensions = match fields.optional(EXTENSIONS_TAG) {
+            Some(x) => Extension::list(Fields::new(x, EXTENSIONS_TAG)?.next()?)?,
+            None => Vec::new(),
+        };
+        fields.end()?;
+
+        let signature_algorithm = AlgorithmIdentifier::from_tlv(outer.next()?)?;
+        let signature = asn1::decode_bit_string(outer.next()?)?;
+        outer.end()?;
+
+        Ok(Certificate {
+            tbs,
+            version,
+            serial,
+            tbs_signature_algorithm,
+            issuer,
+            validity,
+            subject,
+            spki,
+            extensions,
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    /// Returns extension with given identifier
+    pub fn extension(&self, oid: &[u64]) -> Option<&Extension> {
+        self.extensions.iter().find(|x| x.oid == oid)
+    }
+}
+
+/// Attribute of certification request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub oid: Oid,
+    pub values: Vec<Tlv>,
+}
+
+/// PKCS #10 certification request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificationRequest<'a> {
+    /// DER encoding of CertificationRequestInfo, input of signature
+    pub info: &'a [u8],
+    /// Version number, always 1
+    pub version: u8,
+    pub subject: Name,
+    pub spki: SubjectPublicKeyInfo,
+    pub attributes: Vec<Attribute>,
+    pub signature_algorithm: AlgorithmIdentifier,
+    pub signature: BitString,
+}
+
+impl<'a> CertificationRequest<'a> {
+    /// Parses DER encoded certification request
+    pub fn from_der(data: &'a [u8]) -> Result<CertificationRequest<'a>> {
+        let info = signed_part(data)?;
+        let tlv = Tlv::from_vec(data)?;
+
+        let mut outer = Fields::new(&tlv, SEQUENCE_TAG)?;
+        let mut fields = Fields::new(outer.next()?, SEQUENCE_TAG)?;
+
+        if asn1::decode_i64(fields.next()?)? != 0 {
+            return Err(invalid(asn1::INTEGER_TAG));
+        }
+
+        let subject = Name::from_tlv(fields.next()?)?;
+        let spki = SubjectPublicKeyInfo::from_tlv(fields.next()?)?;
+
+        let attributes = Fields::new(fields.next()?, ATTRIBUTES_TAG)?
+            .rest()
+            .iter()
+            .map(|x| {
+                let mut fields = Fields::new(x, SEQUENCE_TAG)?;
+                Ok(Attribute {
+                    oid: asn1::decode_oid(fields.next()?)?,
+                    values: Fields::new(fields.next()?, SET_TAG)?.rest().to_vec(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        fields.end()?;
+
+        let signature_algorithm = AlgorithmIdentifier::from_tlv(outer.next()?)?;
+        let signature = asn1::decode_bit_string(outer.next()?)?;
+        outer.end()?;
+
+        Ok(CertificationRequest {
+            info,
+            version: 1,
+            subject,
+            spki,
+            attributes,
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    /// Returns extensions requested with PKCS #9 extensionRequest attribute
+    pub fn extensions(&self) -> Result<Vec<Extension>> {
+        const EXTENSION_REQUEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 14];
+
+        match self.attributes.iter().find(|x| x.oid == EXTENSION_REQUEST) {
+            Some(attr) => Extension::list(attr.values.first().ok_or(invalid(SET_TAG))?),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// PEM armored block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pem {
+    /// Label of block, e.g. `CERTIFICATE`
+    pub label: String,
+    pub data: Vec<u8>,
+}
+
+/// Decodes all PEM blocks of input, text outside of blocks is ignored
+pub fn decode_pem(input: &str) -> Result<Vec<Pem>> {
+    let mut out = Vec::new();
+    let mut lines = input.lines().map(str::trim);
+
+    while let Some(line) = lines.next() {
+        let label = match line
+            .strip_prefix("-----BEGIN ")
+            .and_then(|x| x.strip_suffix("-----"))
+        {
+            Some(label) => label,
+            None => continue,
+        };
+
+        let mut body = String::new();
+        loop {
+            match lines.next() {
+                Some(x) if x.starts_with("-----END ") => {
+                    if x != alloc::format!("-----END {}-----", label) {
+                        return Err(TlvError::InvalidPem);
+                    }
+                    break;
+                }
+                Some(x) if x.contains(':') => {} // RFC 1421 headers
+                Some(x) => body.push_str(x),
+                None => return Err(TlvError::InvalidPem),
+            }
+        }
+
+        out.push(Pem {
+            label: label.into(),
+            data: decode_base64(&body)?,
+        });
+    }
+
+    Ok(out)
+}
+
+fn decode_base64(input: &str) -> Result<Vec<u8>> {
+    let input = input.as_bytes();
+    if input.len() % 4 != 0 {
+        return Err(TlvError::InvalidPem);
+    }
+
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    for (i, chunk) in input.chunks(4).enumerate() {
+        let last = i == input.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|&&x| x == b'=').count();
+        if padding > 2 || (padding != 0 && !last) {
+            return Err(TlvError::InvalidPem);
+        }
+
+        let mut acc: u32 = 0;
+        for &x in &chunk[..4 - padding] {
+            let v = match x {
+                b'A'..=b'Z' => x - b'A',
+                b'a'..=b'z' => x - b'a' + 26,
+                b'0'..=b'9' => x - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return Err(TlvError::InvalidPem),
+            };
+            acc = acc << 6 | v as u32;
+        }
+        acc <<= 6 * padding;
+
+        out.extend_from_slice(&acc.to_be_bytes()[1..4 - padding]);
+    }
+
+    Ok(out)
+}
+
+/// Returns the first element of SEQUENCE encoded in `data`
+fn signed_part(data: &[u8]) -> Result<&[u8]> {
+    let (header, _) = element_len(data)?;
+    let content = &data[header..];
+
+    let (header, len) = element_len(content)?;
+    let end = header.checked_add(len).ok_or(TlvError::InvalidLength)?;
+    content.get(..end).ok_or(TlvError::TruncatedTlv)
+}
+
+/// Returns length of header and length of value of data object
+fn element_len(data: &[u8]) -> Result<(usize, usize)> {
+    let mut iter = data.iter();
+    Tlv::read_tag(&mut iter)?;
+    let len = Tlv::read_len(&mut iter)?;
+    Ok((data.len() - iter.len(), len))
+}
+
+fn decode_time(tlv: &Tlv) -> Result<DateTime> {
+    match tlv.tag() {
+        asn1::UTC_TIME_TAG => asn1::decode_utc_time(tlv),
+        _ => asn1::decode_generalized_time(tlv),
+    }
+}
+
+fn invalid(tag: Tag) -> TlvError {
+    TlvError::InvalidValue { tag_number: tag }
+}
+
+/// Cursor over children of constructed data object
+struct Fields<'a> {
+    tag: Tag,
+    list: &'a [Tlv],
+}
+
+impl<'a> Fields<'a> {
+    fn new(tlv: &'a Tlv, tag: Tag) -> Result<Fields<'a>> {
+        if tlv.tag() != tag {
+            return Err(TlvError::InvalidTagNumber);
+        }
+
+        let list = match *tlv.val() {
+            Value::TlvList(ref list) => &list[..],
+            _ => &[],
+        };
+
+        Ok(Fields { tag, list })
+    }
+
+    /// Returns next mandatory child
+    fn next(&mut self) -> Result<&'a Tlv> {
+        let (first, rest) = self.list.split_first().ok_or(invalid(self.tag))?;
+        self.list = rest;
+        Ok(first)
+    }
+
+    /// Returns next child if it has given tag
+    fn optional(&mut self, tag: Tag) -> Option<&'a Tlv> {
+        match self.list.first() {
+            Some(x) if x.tag() == tag => self.next().ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns remaining children
+    fn rest(self) -> &'a [Tlv] {
+        self.list
+    }
+
+    /// Checks that all children were read
+    fn end(self) -> Result<()> {
+        match self.list.is_empty() {
+            true => Ok(()),
+            false => Err(invalid(self.tag)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    const CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBXjCCAQSgAwIBAgIFEjRWeJAwCgYIKoZIzj0EAwIwNjELMAkGA1UEBhMCREUx
+ETAPBgNVBAoMCFRlc3QgSFNNMRQwEgYDVQQDDAtUZXJtaW5hbCBDQTAgFw0yNDAx
+MDEwMDAwMDBaGA8yMDU0MDEwMTAwMDAwMFowFjEUMBIGA1UEAwwLdGVybWluYWwt
+MDEwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAQCBdQtj7MUg/MszT+j/lcyunQc
+1uiX86Oyq0Da2HEKXXWbk50jG0tfXclujKSdkiiPTmnnpoRm2ggXxPQiFM63ox0w
+GzAMBgNVHRMBAf8EAjAAMAsGA1UdDwQEAwIHgDAKBggqhkjOPQQDAgNIADBFAiEA
+2XXWeZ+PqosjO1S1gjhz/W/9/pJhx/A9CElGaqgLTeoCICSa3Uwy894jelkc4Y5/
+WeR8KXwRuNa8qKY7Ptny5csJ
+-----END CERTIFICATE-----
+";
+
+    const CSR: &str = "-----BEGIN CERTIFICATE REQUEST-----
+MIHwMIGXAgEAMBYxFDASBgNVBAMMC3Rlcm1pbmFsLTAxMFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAEAgXULY+zFIPzLM0/o/5XMrp0HNbol/OjsqtA2thxCl11m5Od
+IxtLX13JboyknZIoj05p56aEZtoIF8T0IhTOt6AfMB0GCSqGSIb3DQEJDjEQMA4w
+DAYDVR0TAQH/BAIwADAKBggqhkjOPQQDAgNIADBFAiAaHNTvDTNBeMaX1EIEdaxv
+Mb9Yydlmk6tzRS0szXcKGAIhAIyyKrMp+H6B2nua1dlvJOcoKqFpw47dyZVdFWQQ
+zBHT
+-----END CERTIFICATE REQUEST-----
+";
+
+    /// Appends NULL to SEQUENCE found by following the first child `depth` times
+    fn append_null(tlv: &Tlv, depth: usize) -> Tlv {
+        let mut list = match tlv.val() {
+            Value::TlvList(list) => list.clone(),
+            _ => panic!("constructed data object expected"),
+        };
+        match depth {
+            0 => list.push(Tlv::new(asn1::NULL_TAG, Value::Val(Vec::new())).unwrap()),
+            _ => list[0] = append_null(&list[0], depth - 1),
+        }
+        Tlv::new(tlv.tag(), Value::TlvList(list)).unwrap()
+    }
+
+    #[test]
+    fn certificate_test() {
+        let pem = decode_pem(CERT).unwrap();
+        assert_eq!(pem[0].label, "CERTIFICATE");
+
+        let cert = Certificate::from_der(&pem[0].data).unwrap();
+        assert_eq!(cert.tbs[..4], [0x30, 0x82, 0x01, 0x04]);
+        assert_eq!(cert.tbs.len(), 264);
+        assert_eq!(cert.serial, [0x12, 0x34, 0x56, 0x78, 0x90]);
+        assert_eq!(cert.tbs_signature_algorithm, cert.signature_algorithm);
+        assert!(cert.signature_algorithm.parameters.is_none());
+        assert_eq!(cert.subject.to_string(), "CN=terminal-01");
+        assert_eq!(cert.issuer.find(oid::ORGANIZATION_NAME), Some("Test HSM"));
+
+        // notAfter is encoded as GeneralizedTime
+        assert_eq!(
+            cert.validity.not_before,
+            DateTime::new(2024, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            cert.validity.not_after,
+            DateTime::new(2054, 1, 1, 0, 0, 0).unwrap()
+        );
+        assert!(cert
+            .validity
+            .contains(&DateTime::new(2026, 10, 19, 12, 0, 0).unwrap()));
+
+        assert_eq!(cert.spki.algorithm.oid, oid::EC_PUBLIC_KEY);
+        let curve = cert.spki.algorithm.parameters.as_ref().unwrap();
+        assert_eq!(asn1::decode_oid(curve).unwrap(), oid::PRIME256V1);
+        assert_eq!(cert.spki.public_key.data.len(), 65);
+
+        assert_eq!(cert.extensions.len(), 2);
+        let ext = cert.extension(oid::BASIC_CONSTRAINTS).unwrap();
+        assert!(ext.critical);
+        assert_eq!(ext.value, [0x30, 0x00]);
+        let ext = cert.extension(oid::KEY_USAGE).unwrap();
+        assert!(!ext.critical);
+        assert_eq!(ext.value, [0x03, 0x02, 0x07, 0x80]);
+
+        assert_eq!(cert.signature.data[..2], [0x30, 0x45]);
+
+        // truncated certificate
+        assert!(Certificate::from_der(&pem[0].data[..100]).is_err());
+
+        // trailing element after signature or extensions
+        let tlv = Tlv::from_vec(&pem[0].data).unwrap();
+        assert_eq!(tlv.to_vec(), pem[0].data);
+        for depth in 0..2 {
+            assert_eq!(
+                Certificate::from_der(&append_null(&tlv, depth).to_vec()),
+                Err(invalid(SEQUENCE_TAG))
+            );
+        }
+    }
+
+    #[test]
+    fn request_test() {
+        let pem = decode_pem(CSR).unwrap();
+        let csr = CertificationRequest::from_der(&pem[0].data).unwrap();
+
+        assert_eq!(csr.info.len(), 154);
+        assert_eq!(csr.subject.find(oid::COMMON_NAME), Some("terminal-01"));
+        assert_eq!(csr.spki.public_key.data[0], 0x04);
+
+        let extensions = csr.extensions().unwrap();
+        assert_eq!(extensions.len(), 1);
+        assert_eq!(extensions[0].oid, oid::BASIC_CONSTRAINTS);
+
+        // trailing element after signature or attributes
+        let tlv = Tlv::from_vec(&pem[0].data).unwrap();
+        assert_eq!(tlv.to_vec(), pem[0].data);
+        for depth in 0..2 {
+            assert_eq!(
+                CertificationRequest::from_der(&append_null(&tlv, depth).to_vec()),
+                Err(invalid(SEQUENCE_TAG))
+            );
+        }
+
+        // certificate is not a request
+        let cert = decode_pem(CERT).unwrap();
+        assert!(CertificationRequest::from_der(&cert[0].data).is_err());
+    }
+
+    #[test]
+    fn pem_test() {
+        let text = alloc::format!("garbage\n{}\n{}", CERT, CSR);
+        let pem = decode_pem(&text).unwrap();
+        assert_eq!(pem.len(), 2);
+        assert_eq!(pem[1].label, "CERTIFICATE REQUEST");
+
+        let pem = decode_pem("-----BEGIN X-----\nAAEC\nAw==\n-----END X-----").unwrap();
+        assert_eq!(pem[0].data, [0x00, 0x01, 0x02, 0x03]);
+
+        for text in [
+            "-----BEGIN X-----\nAAEC\n-----END Y-----",
+            "-----BEGIN X-----\nAAEC",
+            "-----BEGIN X-----\nAA*C\n-----END X-----",
+            "-----BEGIN X-----\nAAE\n-----END X-----",
+            "-----BEGIN X-----\nAw==AAEC\n-----END X-----",
+        ] {
+            assert!(matches!(decode_pem(text), Err(TlvError::InvalidPem)));
+        }
+    }
+}